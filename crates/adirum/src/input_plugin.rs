@@ -1,19 +1,36 @@
+use std::f32::consts::FRAC_PI_2;
+
 use bevy::prelude::*;
+use blocks::{BlockCoords, BlockTransform};
 use message::{MessageSender, MessageType};
 use render::camera::{Camera, MainCamera};
 
+/// The closest the camera can get to looking straight up or down while
+/// orbiting, in radians.
+const MAX_ORBIT_PITCH: f32 = FRAC_PI_2 - 0.1;
+
 #[derive(Default)]
 pub struct InputPlugin;
 
 impl Plugin for InputPlugin {
   fn build(&self, app: &mut App) {
-    app.add_systems(
+    app.init_resource::<CameraFocus>().add_systems(
       Update,
-      (keyboard_input_app_exit, keyboard_input_move_camera),
+      (
+        keyboard_input_app_exit,
+        keyboard_input_move_camera,
+        keyboard_input_orbit_camera,
+      ),
     );
   }
 }
 
+/// The point the main camera orbits around.
+///
+/// If this is `None`, the camera orbits the centroid of the station's blocks.
+#[derive(Resource, Default)]
+pub struct CameraFocus(pub Option<Vec3>);
+
 fn keyboard_input_app_exit(
   keyboard: Res<ButtonInput<KeyCode>>,
   mut exit: EventWriter<AppExit>,
@@ -72,3 +89,74 @@ fn keyboard_input_move_camera(
     }
   }
 }
+
+fn keyboard_input_orbit_camera(
+  keyboard: Res<ButtonInput<KeyCode>>,
+  mut query: Query<&mut Transform, With<MainCamera>>,
+  focus: Res<CameraFocus>,
+  blocks: Query<(&BlockCoords, Option<&BlockTransform>)>,
+  time: Res<Time>,
+  mut sender: MessageSender,
+) {
+  let orbit_speed = 1.0;
+  let mut yaw_dir = 0.0;
+  let mut pitch_dir = 0.0;
+
+  if keyboard.pressed(KeyCode::ArrowLeft) {
+    yaw_dir -= 1.0;
+  }
+  if keyboard.pressed(KeyCode::ArrowRight) {
+    yaw_dir += 1.0;
+  }
+  if keyboard.pressed(KeyCode::KeyR) {
+    pitch_dir += 1.0;
+  }
+  if keyboard.pressed(KeyCode::KeyF) {
+    pitch_dir -= 1.0;
+  }
+
+  if yaw_dir == 0.0 && pitch_dir == 0.0 {
+    return;
+  }
+
+  let focus_point = focus.0.unwrap_or_else(|| {
+    let (sum, count) = blocks.iter().fold(
+      (Vec3::ZERO, 0),
+      |(sum, count), (coords, block_transform)| {
+        (
+          sum + coords.world_space_block_center(block_transform),
+          count + 1,
+        )
+      },
+    );
+    if count == 0 {
+      Vec3::ZERO
+    } else {
+      sum / count as f32
+    }
+  });
+
+  for mut transform in query.iter_mut() {
+    let yaw = yaw_dir * orbit_speed * time.delta_secs();
+
+    // clamp the camera's elevation above the focus point
+    let offset = transform.translation - focus_point;
+    let current_pitch = (offset.y / offset.length().max(f32::EPSILON))
+      .clamp(-1.0, 1.0)
+      .asin();
+    let target_pitch = (current_pitch
+      + pitch_dir * orbit_speed * time.delta_secs())
+    .clamp(-MAX_ORBIT_PITCH, MAX_ORBIT_PITCH);
+    let pitch = target_pitch - current_pitch;
+
+    // rotating about the camera's right axis moves it down for a positive
+    // angle, so negate to raise it
+    let rotation = Quat::from_rotation_y(yaw)
+      * Quat::from_axis_angle(*transform.right(), -pitch);
+    transform.rotate_around(focus_point, rotation);
+
+    if yaw != 0.0 || pitch != 0.0 {
+      sender.send(MessageType::MutateCameraOrbit { yaw, pitch });
+    }
+  }
+}
@@ -13,8 +13,10 @@ use blocks::{BlockCoords, BlockPlugin, StationBlockType};
 use message::MessagePlugin;
 use render::{
   RenderPlugin,
-  camera::{Camera, MainCamera},
+  camera::{Camera, CameraProjection, MainCamera},
   debug_signage::DebugSign,
+  obj::ObjPlugin,
+  theme::ThemePlugin,
 };
 
 use self::{input_plugin::InputPlugin, ui::UiPlugin};
@@ -26,6 +28,18 @@ fn setup_camera(mut commands: Commands) {
       .looking_to(Vec3::NEG_Z, Vec3::Y),
     MainCamera,
   ));
+
+  // top-down tactical view
+  commands.spawn((
+    Camera {
+      projection: CameraProjection::Orthographic {
+        foreshortening: 0.0,
+      },
+      ..default()
+    }
+    .with_scale(0.1),
+    Transform::from_xyz(0.0, 100.0, 0.0).looking_to(Vec3::NEG_Y, Vec3::NEG_Z),
+  ));
 }
 
 fn setup_station_blocks(mut commands: Commands) {
@@ -46,7 +60,8 @@ fn main() {
   #[cfg(not(feature = "no-vsync"))]
   let frame_period = Duration::from_secs_f64(1.0 / 60.0);
 
-  App::new()
+  let mut app = App::new();
+  app
     .register_type::<Transform>()
     .add_plugins(
       MinimalPlugins.set(ScheduleRunnerPlugin::run_loop(frame_period)),
@@ -54,6 +69,7 @@ fn main() {
     .add_plugins((
       TransformPlugin,
       HierarchyPlugin,
+      AssetPlugin::default(),
       DiagnosticsPlugin,
       FrameTimeDiagnosticsPlugin,
     ))
@@ -66,9 +82,16 @@ fn main() {
       InputPlugin,
       MessagePlugin,
       RenderPlugin,
+      ObjPlugin,
       UiPlugin,
     ))
     .add_systems(Startup, setup_station_blocks)
-    .add_systems(Startup, setup_camera)
-    .run();
+    .add_systems(Startup, setup_camera);
+
+  // e.g. `ADIRUM_THEME=themes/sage.ron`, which is reloaded as it's edited
+  if let Some(path) = std::env::var_os("ADIRUM_THEME") {
+    app.add_plugins(ThemePlugin::new(path));
+  }
+
+  app.run();
 }
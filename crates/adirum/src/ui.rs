@@ -8,29 +8,73 @@ use bevy_ratatui::{error::exit_on_error, terminal::RatatuiContext};
 use diagnostic_bar_widget::DiagnosticBarWidget;
 use message::{MessageLog, MessageLogWidgetAnimationSettings};
 use ratatui::{
-  layout::{Constraint, Layout},
+  buffer::Buffer,
+  layout::{Constraint, Layout, Rect},
   widgets::{Block, StatefulWidget, Widget},
 };
-use render::render_buffer::RenderBuffer;
+use render::{
+  camera::{Camera, MainCamera},
+  render_buffer::RenderBuffer,
+  theme::Theme,
+};
 use rendered_widget::RenderedWidget;
 
-use self::{message_log_widget::MessageLogWidget, styles::BASE_STYLE};
+use self::{message_log_widget::MessageLogWidget, styles::base_style};
+
+type ViewportQuery<'w, 's> = Query<
+  'w,
+  's,
+  (Entity, &'static mut RenderBuffer, Has<MainCamera>),
+  With<Camera>,
+>;
 
 pub struct UiApp<'a> {
-  camera_buffer: ResMut<'a, RenderBuffer>,
+  viewports: ViewportQuery<'a, 'a>,
   diagnostic_store: Res<'a, DiagnosticsStore>,
   message_log: Res<'a, MessageLog>,
   message_log_anim_settings: Res<'a, MessageLogWidgetAnimationSettings>,
   time: Res<'a, Time>,
+  theme: Res<'a, Theme>,
+}
+
+/// Lays out a [`RenderedWidget`] for every camera. The main camera takes up
+/// most of the area, and any other cameras are stacked in a column to its
+/// right.
+fn render_viewports(
+  viewports: &mut ViewportQuery,
+  area: Rect,
+  buf: &mut Buffer,
+) {
+  let mut viewports = viewports.iter_mut().collect::<Vec<_>>();
+  viewports.sort_by_key(|(entity, _, is_main)| (!is_main, *entity));
+
+  let Some(((_, main_buffer, _), secondary)) = viewports.split_first_mut()
+  else {
+    return;
+  };
+
+  if secondary.is_empty() {
+    RenderedWidget.render(area, buf, main_buffer.widget_state_mut());
+    return;
+  }
+
+  let [main_area, side_area] =
+    Layout::horizontal([Constraint::Fill(2), Constraint::Fill(1)]).areas(area);
+  let side_areas =
+    Layout::vertical(secondary.iter().map(|_| Constraint::Fill(1)))
+      .split(side_area);
+
+  RenderedWidget.render(main_area, buf, main_buffer.widget_state_mut());
+  for ((_, buffer, _), area) in secondary.iter_mut().zip(side_areas.iter()) {
+    RenderedWidget.render(*area, buf, buffer.widget_state_mut());
+  }
 }
 
 impl Widget for UiApp<'_> {
-  fn render(
-    mut self,
-    area: ratatui::prelude::Rect,
-    buf: &mut ratatui::prelude::Buffer,
-  ) {
-    Block::new().style(BASE_STYLE).render(area, buf);
+  fn render(mut self, area: Rect, buf: &mut Buffer) {
+    Block::new()
+      .style(base_style(&self.theme))
+      .render(area, buf);
 
     let layout = Layout::vertical([
       Constraint::Length(1),
@@ -39,18 +83,16 @@ impl Widget for UiApp<'_> {
     ])
     .split(area);
 
-    DiagnosticBarWidget::new(self.diagnostic_store).render(layout[0], buf);
+    DiagnosticBarWidget::new(self.diagnostic_store, &self.theme)
+      .render(layout[0], buf);
 
-    RenderedWidget.render(
-      layout[1],
-      buf,
-      self.camera_buffer.widget_state_mut(),
-    );
+    render_viewports(&mut self.viewports, layout[1], buf);
 
     MessageLogWidget::new(
       self.message_log,
       self.message_log_anim_settings,
       self.time,
+      &self.theme,
     )
     .render(layout[2], buf);
   }
@@ -62,32 +104,34 @@ impl Plugin for UiPlugin {
   fn build(&self, app: &mut App) {
     app.add_systems(
       Last,
-      draw_ui
-        .pipe(exit_on_error)
-        .after(render::render_shape_buffers),
+      draw_ui.pipe(exit_on_error).after(render::render_cameras),
     );
   }
 }
 
 pub fn draw_ui(
   mut context: ResMut<RatatuiContext>,
-  camera_buffer: ResMut<RenderBuffer>,
+  viewports: ViewportQuery,
   diagnostic_store: Res<DiagnosticsStore>,
   message_log: Res<MessageLog>,
   message_log_anim_settings: Res<MessageLogWidgetAnimationSettings>,
   time: Res<Time>,
+  theme: Res<Theme>,
 ) -> color_eyre::Result<()> {
+  let color_depth = theme.color_depth;
   context.draw(|frame| -> _ {
     frame.render_widget(
       UiApp {
-        camera_buffer,
+        viewports,
         diagnostic_store,
         message_log,
         message_log_anim_settings,
         time,
+        theme,
       },
       frame.area(),
-    )
+    );
+    color_depth.quantize_buffer(frame.buffer_mut());
   })?;
 
   Ok(())
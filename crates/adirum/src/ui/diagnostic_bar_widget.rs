@@ -7,19 +7,30 @@ use ratatui::{
   prelude::{Rect, *},
   widgets::Block,
 };
-use render::diagnostics::{
-  DRAWN_CELL_COUNT_DIAG_PATH, SHAPE_BUFFER_COUNT_DIAG_PATH,
+use render::{
+  diagnostics::{
+    CULLED_ENTITY_COUNT_DIAG_PATH, DRAWN_CELL_COUNT_DIAG_PATH,
+    REDRAWN_SHAPE_COUNT_DIAG_PATH, SHAPE_BUFFER_COUNT_DIAG_PATH,
+  },
+  theme::Theme,
 };
 
-use super::styles::{DEFAULT_STYLE, DIM_STYLE, PUNCHY_STYLE};
+use super::styles::{default_style, dim_style, punchy_style};
 
 pub struct DiagnosticBarWidget<'a> {
   diagnostic_store: Res<'a, DiagnosticsStore>,
+  theme:            &'a Theme,
 }
 
 impl<'a> DiagnosticBarWidget<'a> {
-  pub fn new(diagnostic_store: Res<'a, DiagnosticsStore>) -> Self {
-    Self { diagnostic_store }
+  pub fn new(
+    diagnostic_store: Res<'a, DiagnosticsStore>,
+    theme: &'a Theme,
+  ) -> Self {
+    Self {
+      diagnostic_store,
+      theme,
+    }
   }
 }
 
@@ -27,6 +38,8 @@ impl Widget for DiagnosticBarWidget<'_> {
   fn render(self, area: Rect, buf: &mut Buffer) {
     let params = &[
       ("SHAPE_BUFFER_COUNT", SHAPE_BUFFER_COUNT_DIAG_PATH),
+      ("CULLED_ENTITY_COUNT", CULLED_ENTITY_COUNT_DIAG_PATH),
+      ("REDRAWN_SHAPE_COUNT", REDRAWN_SHAPE_COUNT_DIAG_PATH),
       ("DRAWN_CELL_COUNT", DRAWN_CELL_COUNT_DIAG_PATH),
       ("FRAME_TIME", FrameTimeDiagnosticsPlugin::FRAME_TIME),
       ("FPS", FrameTimeDiagnosticsPlugin::FPS),
@@ -42,8 +55,11 @@ impl Widget for DiagnosticBarWidget<'_> {
       })
       .map(|(label, value)| {
         Line::from_iter([
-          Span::styled(format!("{label}: "), DIM_STYLE),
-          Span::styled(format!("{:.03}", value.value), PUNCHY_STYLE),
+          Span::styled(format!("{label}: "), dim_style(self.theme)),
+          Span::styled(
+            format!("{:.03}", value.value),
+            punchy_style(self.theme),
+          ),
         ])
       })
       .collect::<Vec<_>>();
@@ -55,7 +71,9 @@ impl Widget for DiagnosticBarWidget<'_> {
     .flex(Flex::End)
     .split(area);
 
-    Block::new().style(DEFAULT_STYLE).render(area, buf);
+    Block::new()
+      .style(default_style(self.theme))
+      .render(area, buf);
 
     lines.into_iter().enumerate().for_each(|(i, l)| {
       l.render(layout[i], buf);
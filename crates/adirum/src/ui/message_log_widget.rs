@@ -5,26 +5,15 @@ use ratatui::{
   style::Color,
   widgets::{Block, BorderType, Paragraph},
 };
+use render::{color_depth::blend_color, theme::Theme};
 
-use super::styles::{BORDER_STYLE, DEFAULT_STYLE, TITLE_STYLE};
-
-fn blend_u8_value(from: u8, to: u8, t: f32) -> u8 {
-  (from as f32 + (to as f32 - from as f32) * t).round() as u8
-}
-
-fn blend_colors(from: (u8, u8, u8), to: (u8, u8, u8), t: f32) -> (u8, u8, u8) {
-  let t = t.clamp(0.0, 1.0);
-  (
-    blend_u8_value(from.0, to.0, t),
-    blend_u8_value(from.1, to.1, t),
-    blend_u8_value(from.2, to.2, t),
-  )
-}
+use super::styles::{border_style, default_style, title_style};
 
 pub struct MessageLogWidget<'a> {
   message_log:   Res<'a, MessageLog>,
   anim_settings: Res<'a, MessageLogWidgetAnimationSettings>,
   time:          Res<'a, Time>,
+  theme:         &'a Theme,
 }
 
 impl<'a> MessageLogWidget<'a> {
@@ -32,17 +21,20 @@ impl<'a> MessageLogWidget<'a> {
     message_log: Res<'a, MessageLog>,
     anim_settings: Res<'a, MessageLogWidgetAnimationSettings>,
     time: Res<'a, Time>,
+    theme: &'a Theme,
   ) -> Self {
     Self {
       message_log,
       anim_settings,
       time,
+      theme,
     }
   }
 }
 
 impl Widget for MessageLogWidget<'_> {
   fn render(self, area: Rect, buf: &mut Buffer) {
+    let default_style = default_style(self.theme);
     let lines = self
       .message_log
       .messages
@@ -53,17 +45,12 @@ impl Widget for MessageLogWidget<'_> {
         let anim_t = message_age
           .div_duration_f32(self.anim_settings.opacity_anim_duration);
 
-        let original_fg_color = match DEFAULT_STYLE.fg {
-          Some(Color::Rgb(r, g, b)) => (r, g, b),
-          _ => panic!("failed to extract FG RGB color from DEFAULT_STYLE"),
-        };
-        let bg_color = match DEFAULT_STYLE.bg {
-          Some(Color::Rgb(r, g, b)) => (r, g, b),
-          _ => panic!("failed to extract BG RGB color from DEFAULT_STYLE"),
-        };
-        let fg_color = blend_colors(bg_color, original_fg_color, anim_t);
-        let style =
-          DEFAULT_STYLE.fg(Color::Rgb(fg_color.0, fg_color.1, fg_color.2));
+        let fg_color = blend_color(
+          default_style.bg.unwrap_or(Color::Reset),
+          default_style.fg.unwrap_or(Color::Reset),
+          anim_t,
+        );
+        let style = default_style.fg(fg_color);
 
         Line::default().spans(vec![
           Span::styled(
@@ -79,10 +66,10 @@ impl Widget for MessageLogWidget<'_> {
     Paragraph::new(Text::from(lines))
       .block(
         Block::bordered()
-          .border_style(BORDER_STYLE)
+          .border_style(border_style(self.theme))
           .border_type(BorderType::Rounded)
-          .title_style(TITLE_STYLE)
-          .style(DEFAULT_STYLE)
+          .title_style(title_style(self.theme))
+          .style(default_style)
           .title("Messages"),
       )
       .render(area, buf);
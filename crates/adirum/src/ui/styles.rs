@@ -1,40 +1,28 @@
-use colors::{
-  BACKGROUND_COLOR_RATATUI, BASE_COLOR_RATATUI, DIM_TEXT_COLOR_RATATUI,
-  NORMAL_BORDER_COLOR_RATATUI, NORMAL_TEXT_COLOR_RATATUI,
-  PUNCHY_TEXT_COLOR_RATATUI, TITLE_COLOR_RATATUI,
-};
 use ratatui::style::{Modifier, Style};
+use render::theme::Theme;
 
-pub const BASE_STYLE: Style = Style {
-  fg:              Some(NORMAL_TEXT_COLOR_RATATUI),
-  bg:              Some(BASE_COLOR_RATATUI),
-  underline_color: None,
-  add_modifier:    Modifier::empty(),
-  sub_modifier:    Modifier::empty(),
-};
+pub fn base_style(theme: &Theme) -> Style {
+  Style::new().fg(theme.normal_text).bg(theme.base)
+}
 
-pub const DEFAULT_STYLE: Style = Style {
-  bg: Some(BACKGROUND_COLOR_RATATUI),
-  ..BASE_STYLE
-};
+pub fn default_style(theme: &Theme) -> Style {
+  base_style(theme).bg(theme.background)
+}
 
-pub const PUNCHY_STYLE: Style = Style {
-  fg: Some(PUNCHY_TEXT_COLOR_RATATUI),
-  add_modifier: Modifier::BOLD,
-  ..DEFAULT_STYLE
-};
+pub fn punchy_style(theme: &Theme) -> Style {
+  default_style(theme)
+    .fg(theme.punchy_text)
+    .add_modifier(Modifier::BOLD)
+}
 
-pub const DIM_STYLE: Style = Style {
-  fg: Some(DIM_TEXT_COLOR_RATATUI),
-  ..DEFAULT_STYLE
-};
+pub fn dim_style(theme: &Theme) -> Style {
+  default_style(theme).fg(theme.dim_text)
+}
 
-pub const BORDER_STYLE: Style = Style {
-  fg: Some(NORMAL_BORDER_COLOR_RATATUI),
-  ..DEFAULT_STYLE
-};
+pub fn border_style(theme: &Theme) -> Style {
+  default_style(theme).fg(theme.normal_border)
+}
 
-pub const TITLE_STYLE: Style = Style {
-  fg: Some(TITLE_COLOR_RATATUI),
-  ..DEFAULT_STYLE
-};
+pub fn title_style(theme: &Theme) -> Style {
+  default_style(theme).fg(theme.title)
+}
@@ -1,5 +1,5 @@
 use bevy::prelude::*;
-use render::{Render, gizmo::Gizmos};
+use render::gizmo::Gizmos;
 
 /// Stores a position in block-space.
 #[derive(Component, Reflect, Debug, Default, Clone, Copy)]
@@ -35,7 +35,7 @@ impl BlockCoords {
 }
 
 /// Stores the local transformation of a block in block-space.
-#[derive(Component, Reflect, Debug, Default, Clone, Copy)]
+#[derive(Component, Reflect, Debug, Default, Clone, Copy, PartialEq)]
 pub struct BlockTransform {
   /// The scale of the block (base block size is [DEFAULT_BLOCK_HALF_EXTENTS]).
   scale:         UVec3,
@@ -61,7 +61,9 @@ fn update_transforms(
   mut query: Query<(&BlockCoords, Option<&BlockTransform>, &mut Transform)>,
 ) {
   for (coords, block_transform, mut transform) in query.iter_mut() {
-    coords.update_transform(block_transform, &mut transform);
+    let mut new_transform = *transform;
+    coords.update_transform(block_transform, &mut new_transform);
+    transform.set_if_neq(new_transform);
   }
 }
 
@@ -82,6 +84,6 @@ impl Plugin for BlockCoordsPlugin {
       .register_type::<BlockCoords>()
       .register_type::<BlockTransform>()
       .add_systems(PostUpdate, update_transforms)
-      .add_systems(Render, debug_block_coords);
+      .add_systems(Update, debug_block_coords);
   }
 }
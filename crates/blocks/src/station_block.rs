@@ -1,13 +1,17 @@
 use bevy::prelude::*;
 use render::{
   Render,
-  shapes::{CanvasArgs, RenderedShape},
+  culling::{ShapeBounds, ShapeVisibility},
+  shapes::{
+    CanvasArgs, CuboidArgs, CuboidStyle, DrawnShape, LinePattern, LineVariant,
+    Material, RenderedShape,
+  },
 };
 
 use crate::{BlockTransform, DEFAULT_BLOCK_HALF_EXTENTS};
 
 #[derive(Component, Reflect)]
-#[require(RenderedShape, BlockTransform)]
+#[require(RenderedShape, BlockTransform, ShapeBounds)]
 pub enum StationBlockType {
   Room,
   QuadRoomXZ,
@@ -24,43 +28,72 @@ impl StationBlockType {
       }
     }
   }
-}
-
-fn update_block_transforms(
-  mut query: Query<(&StationBlockType, &mut BlockTransform)>,
-) {
-  for (sbt, mut bt) in query.iter_mut() {
-    *bt = sbt.block_transform();
-  }
-}
-
-fn render_station_block(
-  canvas_args: CanvasArgs,
-  mut query: Query<(&Transform, &StationBlockType, &mut RenderedShape)>,
-) {
-  use render::shapes::*;
 
-  for (transform, block, mut buffer) in query.iter_mut() {
-    match block {
+  /// The shape this block is drawn with, before its [`BlockTransform`] is
+  /// applied.
+  pub fn cuboid(&self) -> CuboidArgs {
+    match self {
       StationBlockType::Room | StationBlockType::QuadRoomXZ => {
         let cuboid_style = CuboidStyle {
-          line_material:   Material::WallEdge,
-          corner_material: Some(Material::WallCorner),
+          line_material:   Material::wall_edge(),
+          corner_material: Some(Material::wall_corner()),
           face_material:   None,
           line_variant:    LineVariant::Thin,
+          line_pattern:    LinePattern::Solid,
+          hidden_line:     None,
+          face_tints:      None,
+          face_shading:    None,
         };
 
-        let cuboid = CuboidArgs {
+        CuboidArgs {
           half_extents: DEFAULT_BLOCK_HALF_EXTENTS,
           style:        cuboid_style,
-        };
-
-        cuboid.draw(buffer.inner_mut(), &canvas_args, transform);
+        }
       }
     }
   }
 }
 
+fn update_block_transforms(
+  mut query: Query<(
+    Ref<StationBlockType>,
+    &mut BlockTransform,
+    &mut ShapeBounds,
+    &mut RenderedShape,
+  )>,
+) {
+  for (sbt, mut bt, mut bounds, mut shape) in query.iter_mut() {
+    bt.set_if_neq(sbt.block_transform());
+    if let Some(cuboid_bounds) = sbt.cuboid().bounds() {
+      bounds.set_if_neq(cuboid_bounds);
+    }
+    if sbt.is_changed() {
+      shape.invalidate();
+    }
+  }
+}
+
+fn render_station_block(
+  canvas_args: CanvasArgs,
+  mut query: Query<(
+    &Transform,
+    &StationBlockType,
+    &ShapeVisibility,
+    &mut RenderedShape,
+  )>,
+) {
+  for (transform, block, visibility, mut buffer) in query.iter_mut() {
+    if !visibility.is_visible() {
+      continue;
+    }
+
+    let Some(buffer) = buffer.redraw_buffer() else {
+      continue;
+    };
+    block.cuboid().draw(buffer, &canvas_args, transform);
+  }
+}
+
 pub struct StationBlockPlugin;
 
 impl Plugin for StationBlockPlugin {
@@ -13,6 +13,7 @@ pub enum MessageType {
   Custom(String),
   MutateCameraScale(f32),
   MutateCameraMove(Vec3),
+  MutateCameraOrbit { yaw: f32, pitch: f32 },
   SpawnDebugSignChild { parent: Entity },
   DespawnDebugSignChild { parent: Entity, child: Entity },
 }
@@ -25,6 +26,9 @@ impl fmt::Display for MessageType {
         write!(f, "scaling camera: {}x", zoom)
       }
       MessageType::MutateCameraMove(vec) => write!(f, "moving camera: {}", vec),
+      MessageType::MutateCameraOrbit { yaw, pitch } => {
+        write!(f, "orbiting camera: yaw {yaw:.3}, pitch {pitch:.3}")
+      }
       MessageType::SpawnDebugSignChild { parent } => {
         write!(f, "spawning child for debug sign on parent {parent}")
       }
@@ -0,0 +1,222 @@
+//! A benchmark scene for the compositor: a large grid of solid, room-sized
+//! cuboids that overlap heavily on screen, rendered headlessly.
+//!
+//! Whole frames are measured twice: once with the shapes' retained buffers
+//! kept between frames, so that only compositing is left, and once with every
+//! shape redrawn every frame, like before buffers were retained.
+//!
+//! Then compositing alone is measured on the same drawn cells, once with the
+//! [`DepthBuffer`] and once with the `HashMap` compositor it replaced.
+//!
+//! Run with:
+//!
+//! ```sh
+//! cargo run --release -p render --features headless --example compositor_bench
+//! ```
+
+use std::{collections::HashMap, time::Instant};
+
+use bevy::{diagnostic::DiagnosticsStore, prelude::*};
+use ratatui::{
+  buffer::{Buffer, Cell},
+  layout::{Position, Rect},
+};
+use render::{
+  camera::{Camera, CanvasResolution},
+  depth_cue::DepthCue,
+  diagnostics::{
+    CULLED_ENTITY_COUNT_DIAG_PATH, DRAWN_CELL_COUNT_DIAG_PATH,
+    REDRAWN_SHAPE_COUNT_DIAG_PATH,
+  },
+  headless::HeadlessRenderer,
+  shapes::*,
+  theme::Theme,
+};
+
+const SIZE: UVec2 = UVec2::new(240, 80);
+
+const GRID_HALF_SIZE: i32 = 12;
+const WARMUP_FRAMES: usize = 10;
+const MEASURED_FRAMES: usize = 100;
+
+fn main() {
+  let mut renderer = HeadlessRenderer::new(
+    SIZE,
+    Camera::default().with_scale(0.02),
+    Transform::from_xyz(0.0, 20.0, 40.0).looking_at(Vec3::ZERO, Vec3::Y),
+  );
+
+  for x in -GRID_HALF_SIZE..GRID_HALF_SIZE {
+    for z in -GRID_HALF_SIZE..GRID_HALF_SIZE {
+      let cuboid = CuboidArgs {
+        half_extents: Vec3::new(2.0, 1.5, 2.0),
+        style:        CuboidStyle {
+          line_material:   Material::wall_edge(),
+          corner_material: Some(Material::wall_corner()),
+          face_material:   Some(Material::wall_face()),
+          line_variant:    LineVariant::Thin,
+          line_pattern:    LinePattern::Solid,
+          hidden_line:     None,
+          face_tints:      None,
+          face_shading:    None,
+        },
+      };
+      renderer.spawn_shape(
+        cuboid,
+        Transform::from_xyz(x as f32 * 4.0, 0.0, z as f32 * 4.0),
+      );
+    }
+  }
+
+  // retained buffers skip drawing shapes that haven't changed, so this mostly
+  // measures compositing them
+  measure(&mut renderer, "retained", false);
+  // while this measures drawing every shape from scratch as well
+  measure(&mut renderer, "redrawn", true);
+
+  compare_compositors(&mut renderer);
+}
+
+/// Renders the scene, invalidating every shape before each frame if `redraw`
+/// is set, and prints how long frames took.
+fn measure(renderer: &mut HeadlessRenderer, label: &str, redraw: bool) {
+  let render_frame = |renderer: &mut HeadlessRenderer| {
+    if redraw {
+      let world = renderer.app_mut().world_mut();
+      for mut shape in world.query::<&mut RenderedShape>().iter_mut(world) {
+        shape.invalidate();
+      }
+    }
+    renderer.render_frame();
+  };
+
+  for _ in 0..WARMUP_FRAMES {
+    render_frame(renderer);
+  }
+
+  let start = Instant::now();
+  for _ in 0..MEASURED_FRAMES {
+    render_frame(renderer);
+  }
+  let elapsed = start.elapsed();
+
+  let diagnostics = renderer.app_mut().world().resource::<DiagnosticsStore>();
+  let measurement = |path| {
+    diagnostics
+      .get_measurement(path)
+      .map(|m| m.value)
+      .unwrap_or_default()
+  };
+
+  println!(
+    "{label}: {} shapes ({} culled, {} redrawn), {} drawn cells, \
+     {MEASURED_FRAMES} frames: {:.3} ms/frame",
+    (GRID_HALF_SIZE * 2).pow(2),
+    measurement(&CULLED_ENTITY_COUNT_DIAG_PATH),
+    measurement(&REDRAWN_SHAPE_COUNT_DIAG_PATH),
+    measurement(&DRAWN_CELL_COUNT_DIAG_PATH),
+    elapsed.as_secs_f64() * 1000.0 / MEASURED_FRAMES as f64
+  );
+}
+
+/// Composites the shape buffers left by the last frame with both the
+/// [`DepthBuffer`] and the `HashMap` compositor, and prints how long each
+/// took.
+fn compare_compositors(renderer: &mut HeadlessRenderer) {
+  let world = renderer.app_mut().world_mut();
+  let theme = world.resource::<Theme>().clone();
+  let shapes = world
+    .query::<&RenderedShape>()
+    .iter(world)
+    .collect::<Vec<_>>();
+  let buffers = shapes
+    .iter()
+    .filter_map(|shape| shape.active_buffer())
+    .collect::<Vec<_>>();
+
+  let area = Rect::new(0, 0, SIZE.x as _, SIZE.y as _);
+  let mut frame = Buffer::empty(area);
+  let depth_cue = DepthCue::disabled();
+
+  let mut depth_buffer = DepthBuffer::default();
+  let depth_buffer_time = time_per_frame(|| {
+    depth_buffer.reset(SIZE, CanvasResolution::Cell);
+    for buffer in &buffers {
+      depth_buffer.composite(buffer);
+    }
+    depth_buffer.render(&mut frame, &theme, &depth_cue);
+  });
+  let hash_map_time = time_per_frame(|| {
+    composite_with_hash_map(&buffers, &theme, &mut frame);
+  });
+
+  println!(
+    "compositing {} cells: hash map {hash_map_time:.3} ms/frame, depth buffer \
+     {depth_buffer_time:.3} ms/frame",
+    buffers.iter().map(|buffer| buffer.len()).sum::<usize>(),
+  );
+}
+
+/// Runs `f` for the warmup frames and then the measured ones, returning how
+/// long the measured ones took on average, in milliseconds.
+fn time_per_frame(mut f: impl FnMut()) -> f64 {
+  for _ in 0..WARMUP_FRAMES {
+    f();
+  }
+  let start = Instant::now();
+  for _ in 0..MEASURED_FRAMES {
+    f();
+  }
+  start.elapsed().as_secs_f64() * 1000.0 / MEASURED_FRAMES as f64
+}
+
+/// The cells nearest the camera at a position, front first.
+type FrontCells<'a> = [Option<(f32, &'a DrawnMaterial)>; 2];
+
+/// The compositor that [`DepthBuffer`] replaced: every buffer is merged into
+/// one list of cells, which is sorted into a map keeping the front two cells
+/// at each position, and the map is rendered into a fresh buffer that's
+/// merged into `frame`. It predates occluders, so it keeps the front two
+/// cells whatever they are.
+fn composite_with_hash_map(
+  buffers: &[&ShapeBuffer],
+  theme: &Theme,
+  frame: &mut Buffer,
+) {
+  let capacity = buffers.iter().map(|buffer| buffer.len()).sum();
+  let mut merged = Vec::with_capacity(capacity);
+  for buffer in buffers {
+    merged.extend(buffer.cells());
+  }
+
+  let mut map = HashMap::<IVec2, FrontCells>::new();
+  for (position, depth, mat) in merged {
+    if !(0.0..=1.0).contains(&depth) {
+      continue;
+    }
+    let front = map.entry(position).or_default();
+    match *front {
+      [Some((a, _)), _] if depth < a => {
+        *front = [Some((depth, mat)), front[0]];
+      }
+      [Some(_), Some((b, _))] if depth >= b => {}
+      [Some(_), _] => front[1] = Some((depth, mat)),
+      [None, _] => front[0] = Some((depth, mat)),
+    }
+  }
+
+  let mut rendered = Buffer::filled(frame.area, Cell::default());
+  for (position, front) in map {
+    let (Ok(x), Ok(y)) = (position.x.try_into(), position.y.try_into()) else {
+      continue;
+    };
+    let position = Position::new(x, y);
+    if !rendered.area.contains(position) {
+      continue;
+    }
+    if let [Some((_, mat)), behind] = front {
+      rendered[position] = mat.render(behind.map(|(_, b)| b), theme);
+    }
+  }
+  frame.merge(&rendered);
+}
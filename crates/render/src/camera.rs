@@ -1,20 +1,77 @@
 use bevy::prelude::*;
 
-use super::{MAX_PROJECTED_DEPTH, render_buffer::RenderBufferSize};
+use super::{
+  MAX_PROJECTED_DEPTH,
+  depth_cue::DepthCue,
+  render_buffer::{RenderBuffer, RenderBufferSize},
+};
 
-/// Standard orthographic camera
+/// Standard camera, orthographic by default.
 #[derive(Component, Reflect, Clone)]
-#[require(Transform, CameraMatrix)]
+#[require(Transform, CameraMatrix, RenderBuffer, RenderBufferSize, DepthCue)]
 pub struct Camera {
   /// The aspect ratio of the terminal characters.
   ///
   /// This, combined with the render buffer's aspect ratio, determines the
-  /// aspect ratio of the orthographic projection.
+  /// aspect ratio of the projection.
   pub character_aspect_ratio: f32,
   /// The scale of the camera.
+  ///
+  /// In perspective mode, this acts as a zoom factor on top of the field of
+  /// view.
   pub scale:                  f32,
-  /// The foreshortening angle, expressed in radians.
-  pub foreshortening:         f32,
+  /// The kind of projection the camera uses.
+  pub projection:             CameraProjection,
+  /// How finely shapes are rasterized within each terminal cell.
+  pub resolution:             CanvasResolution,
+}
+
+/// The kind of projection a [`Camera`] uses.
+#[derive(Reflect, Clone, Debug)]
+pub enum CameraProjection {
+  /// An orthographic projection with a cabinet shear.
+  Orthographic {
+    /// The foreshortening angle, expressed in radians.
+    foreshortening: f32,
+  },
+  /// A true perspective projection.
+  Perspective {
+    /// The vertical field of view, expressed in radians.
+    fov:  f32,
+    /// The distance to the near clipping plane.
+    near: f32,
+  },
+}
+
+/// How finely a [`Camera`] rasterizes shapes within each terminal cell.
+#[derive(Reflect, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum CanvasResolution {
+  /// One canvas point per cell, drawn with the shapes' own glyphs.
+  #[default]
+  Cell,
+  /// A 2x4 grid of canvas points per cell, drawn as braille dots.
+  Braille,
+  /// Two canvas points stacked in each cell, drawn as colored half blocks.
+  HalfBlock,
+}
+
+impl CanvasResolution {
+  /// The number of canvas points across and down each terminal cell.
+  pub fn points_per_cell(&self) -> UVec2 {
+    match self {
+      CanvasResolution::Cell => UVec2::ONE,
+      CanvasResolution::Braille => UVec2::new(2, 4),
+      CanvasResolution::HalfBlock => UVec2::new(1, 2),
+    }
+  }
+}
+
+impl Default for CameraProjection {
+  fn default() -> Self {
+    CameraProjection::Orthographic {
+      foreshortening: -1.0 / 3.0,
+    }
+  }
 }
 
 impl Default for Camera {
@@ -23,7 +80,8 @@ impl Default for Camera {
       // charachter height in `em` is 1.2, and width is 0.5
       character_aspect_ratio: 5.0 / 13.0,
       scale:                  1.0,
-      foreshortening:         -1.0 / 3.0,
+      projection:             CameraProjection::default(),
+      resolution:             CanvasResolution::default(),
     }
   }
 }
@@ -32,8 +90,14 @@ impl Camera {
   pub fn scale(&self) -> f32 { self.scale }
   pub fn set_scale(&mut self, scale: f32) { self.scale = scale; }
   pub fn with_scale(self, scale: f32) -> Self { Self { scale, ..self } }
+  pub fn with_projection(self, projection: CameraProjection) -> Self {
+    Self { projection, ..self }
+  }
+  pub fn with_resolution(self, resolution: CanvasResolution) -> Self {
+    Self { resolution, ..self }
+  }
 
-  /// Calculates an orthogonal projection matrix for the camera.
+  /// Calculates the projection matrix for the camera.
   pub fn calculate_matrix(
     &self,
     camera_transform: &Transform,
@@ -41,6 +105,35 @@ impl Camera {
   ) -> CameraMatrix {
     let aspect_ratio =
       render_buffer_size.aspect_ratio() * self.character_aspect_ratio;
+
+    let (proj, near) = match self.projection {
+      CameraProjection::Orthographic { foreshortening } => {
+        (self.orthographic_matrix(aspect_ratio, foreshortening), 0.0)
+      }
+      CameraProjection::Perspective { fov, near } => {
+        let proj = Mat4::from_scale(Vec3::new(self.scale, self.scale, 1.0))
+          * Mat4::perspective_rh(fov, aspect_ratio, near, MAX_PROJECTED_DEPTH);
+        (proj, near)
+      }
+    };
+
+    let view = camera_transform.compute_matrix().inverse();
+
+    CameraMatrix {
+      proj,
+      view,
+      near,
+      far: MAX_PROJECTED_DEPTH,
+      character_aspect_ratio: self.character_aspect_ratio,
+      resolution: self.resolution,
+    }
+  }
+
+  fn orthographic_matrix(
+    &self,
+    aspect_ratio: f32,
+    foreshortening: f32,
+  ) -> Mat4 {
     let ortho_height = self.scale.recip();
     let ortho_width = ortho_height * aspect_ratio;
 
@@ -58,48 +151,87 @@ impl Camera {
       Vec4::new(1.0, 0.0, 0.0, 0.0),
       Vec4::new(0.0, 1.0, 0.0, 0.0),
       Vec4::new(
-        -shear_angle.cos() * self.foreshortening,
-        -shear_angle.sin() * self.foreshortening,
+        -shear_angle.cos() * foreshortening,
+        -shear_angle.sin() * foreshortening,
         1.0,
         0.0,
       ),
       Vec4::new(0.0, 0.0, 0.0, 1.0),
     );
     proj *= cabinet;
-
-    let view = camera_transform.compute_matrix().inverse();
-
-    CameraMatrix {
-      proj,
-      view,
-      character_aspect_ratio: self.character_aspect_ratio,
-    }
+    proj
   }
 }
 
-#[derive(Component, Reflect, Clone, Debug, Default)]
+#[derive(Component, Reflect, Clone, Debug, Default, PartialEq)]
 pub struct CameraMatrix {
   proj:                   Mat4,
   view:                   Mat4,
+  /// The view-space distance that maps to a depth of 0.
+  near:                   f32,
+  /// The view-space distance that maps to a depth of 1.
+  far:                    f32,
   character_aspect_ratio: f32,
+  resolution:             CanvasResolution,
 }
 
 impl CameraMatrix {
   pub fn world_to_view(&self, point: Vec3) -> Vec3 {
     self.view.transform_point3(point)
   }
+  /// Projects a view-space point into NDC.
+  ///
+  /// The depth is always linear between the near and far planes, regardless
+  /// of projection, so depth sorting and fading behave the same in both
+  /// modes.
   pub fn view_to_ndc(&self, point: Vec3) -> Vec3 {
-    self.proj.transform_point3(point)
+    let ndc = self.proj.project_point3(point);
+    ndc.xy().extend(self.view_depth(point))
+  }
+  /// The linear depth of a view-space point, which is 0 at the near plane and
+  /// 1 at the far plane.
+  pub fn view_depth(&self, point: Vec3) -> f32 {
+    (-point.z - self.near) / (self.far - self.near)
+  }
+  /// Where the camera is in world space, as a homogeneous point.
+  ///
+  /// For perspective cameras, `w` is 1. Orthographic cameras look from
+  /// infinitely far away, so `w` is 0 and `xyz` points back towards the
+  /// camera along its rays.
+  pub fn camera_center(&self) -> Vec4 {
+    // the camera center is the one point that projects to nowhere: its clip
+    // space x, y and w are all 0
+    let center = self.proj.inverse() * Vec4::Z;
+    let center = if center.w.abs() > f32::EPSILON {
+      center / center.w
+    } else if center.z < 0.0 {
+      // the camera looks down -Z, so the way back to it is +Z
+      -center
+    } else {
+      center
+    };
+    self.view.inverse() * center
+  }
+  /// Projects a view-space point into homogeneous clip space, before the
+  /// perspective divide.
+  pub fn view_to_clip(&self, point: Vec3) -> Vec4 {
+    self.proj * point.extend(1.0)
   }
   pub fn world_to_ndc(&self, point: Vec3) -> Vec3 {
     self.view_to_ndc(self.world_to_view(point))
   }
   pub fn character_aspect_ratio(&self) -> f32 { self.character_aspect_ratio }
+  pub fn resolution(&self) -> CanvasResolution { self.resolution }
 }
 
 #[derive(Resource, Clone, Debug, Default, Deref)]
 pub struct MainCameraMatrix(CameraMatrix);
 
+/// The matrix of the camera currently being rendered in the
+/// [`Render`](crate::Render) schedule.
+#[derive(Resource, Clone, Debug, Default, Deref)]
+pub struct ActiveCameraMatrix(pub(crate) CameraMatrix);
+
 #[derive(Component, Reflect)]
 pub struct MainCamera;
 
@@ -107,17 +239,23 @@ pub(crate) fn update_camera_matrices(
   mut query: Query<(
     &Camera,
     &Transform,
+    &RenderBufferSize,
     &mut CameraMatrix,
     Option<&MainCamera>,
   )>,
-  render_buffer_size: Res<RenderBufferSize>,
   mut main_camera_matrix: ResMut<MainCameraMatrix>,
 ) {
-  for (camera, camera_transform, mut existing_matrix, main_camera) in
-    query.iter_mut()
+  for (
+    camera,
+    camera_transform,
+    render_buffer_size,
+    mut existing_matrix,
+    main_camera,
+  ) in query.iter_mut()
   {
-    *existing_matrix =
-      camera.calculate_matrix(camera_transform, &render_buffer_size);
+    existing_matrix.set_if_neq(
+      camera.calculate_matrix(camera_transform, render_buffer_size),
+    );
 
     if main_camera.is_some() {
       main_camera_matrix.0 = existing_matrix.clone();
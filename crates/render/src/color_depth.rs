@@ -0,0 +1,346 @@
+//! Fitting colors to what the terminal can show.
+//!
+//! Everything is drawn in RGB, and the finished buffers are quantized to the
+//! nearest colors of the terminal's [`ColorDepth`] afterwards, so terminals
+//! without truecolor support (like many `tmux` or `ssh` sessions) still get a
+//! sensible picture.
+
+use std::{env, fmt, str::FromStr};
+
+use bevy::prelude::*;
+use ratatui::{buffer::Buffer, style::Color};
+
+/// The environment variable that overrides the detected color depth, set to
+/// `truecolor`, `256` or `16`.
+pub const COLOR_DEPTH_VAR: &str = "ADIRUM_COLOR_DEPTH";
+
+/// The RGB values of the 16 ANSI colors, in the order of their indices, as
+/// `xterm` shows them by default.
+const ANSI_COLORS: [(Color, [u8; 3]); 16] = [
+  (Color::Black, [0, 0, 0]),
+  (Color::Red, [128, 0, 0]),
+  (Color::Green, [0, 128, 0]),
+  (Color::Yellow, [128, 128, 0]),
+  (Color::Blue, [0, 0, 128]),
+  (Color::Magenta, [128, 0, 128]),
+  (Color::Cyan, [0, 128, 128]),
+  (Color::Gray, [192, 192, 192]),
+  (Color::DarkGray, [128, 128, 128]),
+  (Color::LightRed, [255, 0, 0]),
+  (Color::LightGreen, [0, 255, 0]),
+  (Color::LightYellow, [255, 255, 0]),
+  (Color::LightBlue, [0, 0, 255]),
+  (Color::LightMagenta, [255, 0, 255]),
+  (Color::LightCyan, [0, 255, 255]),
+  (Color::White, [255, 255, 255]),
+];
+
+/// The levels of each channel in the 6x6x6 color cube of the 256-color
+/// palette, which starts at index 16.
+const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+/// The index of the first of the 24 grays at the end of the 256-color palette.
+const FIRST_GRAY_INDEX: u8 = 232;
+
+/// How many colors the terminal can show.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ColorDepth {
+  /// Any RGB color.
+  #[default]
+  TrueColor,
+  /// The 256-color `xterm` palette.
+  Indexed256,
+  /// The 16 ANSI colors, whose exact look is up to the terminal.
+  Ansi16,
+}
+
+impl ColorDepth {
+  /// Detects the terminal's color depth from `COLORTERM` and `TERM`, unless
+  /// it's overridden with [`COLOR_DEPTH_VAR`].
+  pub fn detect() -> Self {
+    if let Some(depth) = Self::from_env_override() {
+      return depth;
+    }
+
+    let colorterm = env::var("COLORTERM").unwrap_or_default();
+    if colorterm == "truecolor" || colorterm == "24bit" {
+      return ColorDepth::TrueColor;
+    }
+
+    match env::var("TERM") {
+      Ok(term) if term.contains("direct") => ColorDepth::TrueColor,
+      Ok(term) if term.contains("256color") => ColorDepth::Indexed256,
+      Ok(_) => ColorDepth::Ansi16,
+      // nothing to go on, like on Windows, so assume a modern terminal
+      Err(_) => ColorDepth::TrueColor,
+    }
+  }
+
+  /// The color depth set with [`COLOR_DEPTH_VAR`], if it's set. An invalid
+  /// value is logged and ignored.
+  pub fn from_env_override() -> Option<Self> {
+    Self::parse_override(&env::var(COLOR_DEPTH_VAR).ok()?)
+  }
+
+  /// Parses the value of [`COLOR_DEPTH_VAR`], logging it if it's invalid.
+  fn parse_override(value: &str) -> Option<Self> {
+    value
+      .parse()
+      .inspect_err(|e| warn!("ignoring `{COLOR_DEPTH_VAR}`: {e}"))
+      .ok()
+  }
+
+  /// Whether every RGB color can be shown as-is.
+  pub fn is_true_color(self) -> bool { self == ColorDepth::TrueColor }
+
+  /// The nearest color to `color` that the terminal can show. Named colors and
+  /// [`Color::Reset`] are left alone, since the terminal picks how they look.
+  pub fn quantize(self, color: Color) -> Color {
+    match (self, color) {
+      (ColorDepth::TrueColor, _) => color,
+      (ColorDepth::Indexed256, Color::Rgb(r, g, b)) => {
+        Color::Indexed(nearest_indexed([r, g, b]))
+      }
+      (ColorDepth::Ansi16, Color::Rgb(..) | Color::Indexed(_)) => {
+        to_rgb(color).map_or(color, nearest_ansi)
+      }
+      _ => color,
+    }
+  }
+
+  /// Quantizes the colors of every cell in `buffer`.
+  pub fn quantize_buffer(self, buffer: &mut Buffer) {
+    if self.is_true_color() {
+      return;
+    }
+
+    for cell in buffer.content.iter_mut() {
+      cell.fg = self.quantize(cell.fg);
+      cell.bg = self.quantize(cell.bg);
+      cell.underline_color = self.quantize(cell.underline_color);
+    }
+  }
+}
+
+/// An invalid color depth name.
+#[derive(Debug)]
+pub struct ParseColorDepthError(String);
+
+impl fmt::Display for ParseColorDepthError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(
+      f,
+      "`{}` isn't a color depth, expected `truecolor`, `256` or `16`",
+      self.0
+    )
+  }
+}
+
+impl std::error::Error for ParseColorDepthError {}
+
+impl FromStr for ColorDepth {
+  type Err = ParseColorDepthError;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    match s.trim().to_ascii_lowercase().as_str() {
+      "truecolor" | "24bit" => Ok(ColorDepth::TrueColor),
+      "256" => Ok(ColorDepth::Indexed256),
+      "16" => Ok(ColorDepth::Ansi16),
+      _ => Err(ParseColorDepthError(s.to_string())),
+    }
+  }
+}
+
+/// The RGB value of `color`, or `None` for [`Color::Reset`]. Named and low
+/// indexed colors use `xterm`'s defaults.
+pub fn to_rgb(color: Color) -> Option<[u8; 3]> {
+  match color {
+    Color::Reset => None,
+    Color::Rgb(r, g, b) => Some([r, g, b]),
+    Color::Indexed(i) => Some(indexed_to_rgb(i)),
+    named => ANSI_COLORS
+      .iter()
+      .find(|(ansi, _)| *ansi == named)
+      .map(|(_, rgb)| *rgb),
+  }
+}
+
+fn blend_u8_value(from: u8, to: u8, t: f32) -> u8 {
+  (from as f32 + (to as f32 - from as f32) * t).round() as u8
+}
+
+/// Blends from one color to another. Colors without an RGB value, like
+/// [`Color::Reset`], can't be blended, so they switch over halfway.
+pub fn blend_color(from: Color, to: Color, t: f32) -> Color {
+  let t = t.clamp(0.0, 1.0);
+  match (to_rgb(from), to_rgb(to)) {
+    (Some([fr, fg, fb]), Some([tr, tg, tb])) => Color::Rgb(
+      blend_u8_value(fr, tr, t),
+      blend_u8_value(fg, tg, t),
+      blend_u8_value(fb, tb, t),
+    ),
+    _ if t < 0.5 => from,
+    _ => to,
+  }
+}
+
+fn indexed_to_rgb(index: u8) -> [u8; 3] {
+  match index {
+    0..16 => ANSI_COLORS[index as usize].1,
+    16..FIRST_GRAY_INDEX => {
+      let cube = index - 16;
+      [cube / 36, cube / 6 % 6, cube % 6].map(|i| CUBE_LEVELS[i as usize])
+    }
+    _ => [8 + (index - FIRST_GRAY_INDEX) * 10; 3],
+  }
+}
+
+fn distance_squared(a: [u8; 3], b: [u8; 3]) -> u32 {
+  a.iter()
+    .zip(b)
+    .map(|(a, b)| (*a as i32 - b as i32).pow(2) as u32)
+    .sum()
+}
+
+/// The nearest entry of the 256-color palette's cube and grays. The first 16
+/// entries are skipped, since terminals often remap them.
+fn nearest_indexed(rgb: [u8; 3]) -> u8 {
+  let nearest_level = |channel: u8| {
+    (0..CUBE_LEVELS.len())
+      .min_by_key(|i| (CUBE_LEVELS[*i] as i32 - channel as i32).abs())
+      .unwrap() as u8
+  };
+  let [r, g, b] = rgb.map(nearest_level);
+  let cube_index = 16 + r * 36 + g * 6 + b;
+
+  let average = rgb.iter().map(|c| *c as u32).sum::<u32>() / 3;
+  let gray_index =
+    FIRST_GRAY_INDEX + (average.saturating_sub(3) / 10).min(23) as u8;
+
+  [cube_index, gray_index]
+    .into_iter()
+    .min_by_key(|i| distance_squared(rgb, indexed_to_rgb(*i)))
+    .unwrap()
+}
+
+fn nearest_ansi(rgb: [u8; 3]) -> Color {
+  ANSI_COLORS
+    .iter()
+    .min_by_key(|(_, ansi)| distance_squared(rgb, *ansi))
+    .map(|(color, _)| *color)
+    .unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+  use ratatui::{buffer::Cell, layout::Rect};
+
+  use super::*;
+
+  #[test]
+  fn maps_rgb_to_the_256_color_cube() {
+    assert_eq!(nearest_indexed([0, 0, 0]), 16);
+    assert_eq!(nearest_indexed([255, 255, 255]), 231);
+    assert_eq!(nearest_indexed([255, 0, 0]), 196);
+    assert_eq!(nearest_indexed([0, 255, 0]), 46);
+    assert_eq!(nearest_indexed([0, 0, 255]), 21);
+    assert_eq!(nearest_indexed([250, 100, 10]), 202);
+  }
+
+  #[test]
+  fn maps_grays_to_the_256_color_grays() {
+    assert_eq!(nearest_indexed([8, 8, 8]), FIRST_GRAY_INDEX);
+    assert_eq!(nearest_indexed([128, 128, 128]), 244);
+    assert_eq!(nearest_indexed([238, 238, 238]), 255);
+  }
+
+  #[test]
+  fn maps_rgb_to_ansi_colors() {
+    assert_eq!(nearest_ansi([0, 0, 0]), Color::Black);
+    assert_eq!(nearest_ansi([255, 255, 255]), Color::White);
+    assert_eq!(nearest_ansi([250, 10, 10]), Color::LightRed);
+    assert_eq!(nearest_ansi([120, 0, 0]), Color::Red);
+    assert_eq!(nearest_ansi([180, 180, 200]), Color::Gray);
+  }
+
+  #[test]
+  fn quantizes_only_what_the_depth_cant_show() {
+    let orange = Color::Rgb(250, 100, 10);
+    assert_eq!(ColorDepth::TrueColor.quantize(orange), orange);
+    assert_eq!(ColorDepth::Indexed256.quantize(orange), Color::Indexed(202));
+    assert_eq!(
+      ColorDepth::Ansi16.quantize(Color::Indexed(196)),
+      Color::LightRed
+    );
+
+    for depth in [ColorDepth::Indexed256, ColorDepth::Ansi16] {
+      assert_eq!(depth.quantize(Color::Reset), Color::Reset);
+      assert_eq!(depth.quantize(Color::Cyan), Color::Cyan);
+    }
+  }
+
+  #[test]
+  fn quantizes_every_cell_of_a_buffer() {
+    let mut cell = Cell::default();
+    cell.fg = Color::Rgb(255, 0, 0);
+    cell.bg = Color::Rgb(0, 0, 0);
+    let mut buffer = Buffer::filled(Rect::new(0, 0, 2, 2), cell);
+
+    ColorDepth::Indexed256.quantize_buffer(&mut buffer);
+    assert!(buffer.content.iter().all(|cell| {
+      cell.fg == Color::Indexed(196) && cell.bg == Color::Indexed(16)
+    }));
+  }
+
+  #[test]
+  fn blends_any_colors_with_rgb_values() {
+    let blended =
+      blend_color(Color::Rgb(0, 0, 0), Color::Rgb(200, 100, 50), 0.5);
+    assert_eq!(blended, Color::Rgb(100, 50, 25));
+    // named and indexed colors blend through their `xterm` values
+    assert_eq!(
+      blend_color(Color::Red, Color::Blue, 0.5),
+      Color::Rgb(64, 0, 64)
+    );
+    assert_eq!(
+      blend_color(Color::Indexed(16), Color::Indexed(231), 1.0),
+      Color::Rgb(255, 255, 255)
+    );
+    // and `t` is clamped
+    assert_eq!(
+      blend_color(Color::Black, Color::White, 2.0),
+      Color::Rgb(255, 255, 255)
+    );
+  }
+
+  #[test]
+  fn switches_between_colors_without_rgb_values() {
+    let rgb = Color::Rgb(200, 100, 50);
+    assert_eq!(blend_color(Color::Reset, rgb, 0.25), Color::Reset);
+    assert_eq!(blend_color(Color::Reset, rgb, 0.75), rgb);
+    assert_eq!(blend_color(rgb, Color::Reset, 0.25), rgb);
+    assert_eq!(blend_color(rgb, Color::Reset, 0.75), Color::Reset);
+  }
+
+  #[test]
+  fn parses_the_env_override() {
+    assert_eq!(
+      ColorDepth::parse_override("truecolor"),
+      Some(ColorDepth::TrueColor)
+    );
+    assert_eq!(
+      ColorDepth::parse_override("24bit"),
+      Some(ColorDepth::TrueColor)
+    );
+    assert_eq!(
+      ColorDepth::parse_override(" 256 "),
+      Some(ColorDepth::Indexed256)
+    );
+    assert_eq!(ColorDepth::parse_override("16"), Some(ColorDepth::Ansi16));
+    assert_eq!(
+      ColorDepth::parse_override("TrueColor"),
+      Some(ColorDepth::TrueColor)
+    );
+    assert_eq!(ColorDepth::parse_override("8"), None);
+    assert_eq!(ColorDepth::parse_override(""), None);
+  }
+}
@@ -0,0 +1,112 @@
+use bevy::prelude::*;
+
+use crate::shapes::CanvasArgs;
+
+/// A local-space bounding box for a shape, used to skip drawing entities that
+/// are entirely off screen.
+#[derive(Component, Reflect, Clone, Copy, Debug, Default, PartialEq)]
+pub struct ShapeBounds {
+  /// The center of the bounding box.
+  pub center:       Vec3,
+  /// The half extents of the bounding box.
+  pub half_extents: Vec3,
+}
+
+impl ShapeBounds {
+  pub fn new(center: Vec3, half_extents: Vec3) -> Self {
+    Self {
+      center,
+      half_extents,
+    }
+  }
+
+  /// Creates the smallest bounds containing all of the given points.
+  pub fn from_points(points: impl IntoIterator<Item = Vec3>) -> Self {
+    let (min, max) = points
+      .into_iter()
+      .fold((Vec3::INFINITY, Vec3::NEG_INFINITY), |(min, max), p| {
+        (min.min(p), max.max(p))
+      });
+    if min.cmpgt(max).any() {
+      return Self::default();
+    }
+    Self::new((min + max) / 2.0, (max - min) / 2.0)
+  }
+
+  /// Returns the 8 corners of the bounding box.
+  pub fn corners(&self) -> [Vec3; 8] {
+    std::array::from_fn(|i| {
+      let direction_mask = Vec3::select(
+        BVec3::new(i & 1 == 1, i & 2 == 2, i & 4 == 4),
+        Vec3::ONE,
+        Vec3::NEG_ONE,
+      );
+      self.center + self.half_extents * direction_mask
+    })
+  }
+
+  /// Whether the bounds, transformed by `transform`, lie entirely outside of
+  /// the canvas or the visible depth range.
+  pub fn is_outside_canvas(
+    &self,
+    args: &CanvasArgs,
+    transform: &Transform,
+  ) -> bool {
+    let ndc_corners = self
+      .corners()
+      .map(|c| args.world_to_ndc(transform.transform_point(c)));
+
+    // corners behind the camera don't project sensibly, so only cull these
+    // bounds by depth if every corner is behind
+    if ndc_corners.iter().all(|c| c.z < 0.0) {
+      return true;
+    }
+    if ndc_corners.iter().any(|c| c.z < 0.0) {
+      return false;
+    }
+
+    ndc_corners.iter().all(|c| c.x < -1.0)
+      || ndc_corners.iter().all(|c| c.x > 1.0)
+      || ndc_corners.iter().all(|c| c.y < -1.0)
+      || ndc_corners.iter().all(|c| c.y > 1.0)
+      || ndc_corners.iter().all(|c| c.z > 1.0)
+  }
+}
+
+/// Whether a shape is visible to the camera currently being rendered.
+///
+/// Shapes without [`ShapeBounds`] are always visible.
+#[derive(Component, Reflect, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ShapeVisibility {
+  visible: bool,
+}
+
+impl Default for ShapeVisibility {
+  fn default() -> Self { Self { visible: true } }
+}
+
+impl ShapeVisibility {
+  pub fn is_visible(&self) -> bool { self.visible }
+}
+
+/// Updates the visibility of every bounded shape for the camera currently
+/// being rendered.
+///
+/// Returns the number of culled entities.
+pub(crate) fn cull_shapes(
+  canvas_args: CanvasArgs,
+  mut query: Query<(&ShapeBounds, &Transform, &mut ShapeVisibility)>,
+) -> usize {
+  let mut culled_count = 0;
+
+  for (bounds, transform, mut visibility) in query.iter_mut() {
+    let visible = !bounds.is_outside_canvas(&canvas_args, transform);
+    visibility.set_if_neq(ShapeVisibility { visible });
+
+    if !visible {
+      culled_count += 1;
+    }
+  }
+
+  culled_count
+}
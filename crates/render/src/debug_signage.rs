@@ -8,6 +8,7 @@ use ratatui::{
 use crate::{
   Render,
   shapes::{CanvasArgs, RenderedShape},
+  theme::Theme,
 };
 
 #[derive(Debug, Component, Default)]
@@ -17,30 +18,24 @@ pub struct DebugSign {
 }
 
 impl DebugSign {
-  fn render(&self) -> Paragraph {
+  fn render(&self, theme: &Theme) -> Paragraph<'_> {
     Paragraph::new(Text::from_iter(
       self.infos.iter().flat_map(|s| Text::from(s.clone()).lines),
     ))
-    .bg(colors::BACKGROUND_COLOR_RATATUI)
-    .fg(colors::NORMAL_TEXT_COLOR_RATATUI)
+    .bg(theme.background)
+    .fg(theme.normal_text)
     .wrap(Wrap { trim: false })
     .block(
       Block::bordered()
         .border_type(BorderType::Rounded)
-        .title("Entity Debug".fg(colors::TITLE_COLOR_RATATUI))
-        .fg(colors::NORMAL_BORDER_COLOR_RATATUI),
+        .title("Entity Debug".fg(theme.title))
+        .fg(theme.normal_border),
     )
   }
 }
 
 pub(crate) struct DebugSignPlugin;
 
-fn clear_infos(mut query: Query<&mut DebugSign>) {
-  for mut dsc in query.iter_mut() {
-    dsc.infos.clear();
-  }
-}
-
 /// For each entity with a `DebugSignRequired`, find all the children with a
 /// `DebugSignChild`, and add the info from the parent to the `DebugSignChild`.
 #[allow(clippy::type_complexity)]
@@ -59,22 +54,45 @@ fn propagate_infos(world: &mut World) {
     let Some(mut ds) = world.get_mut::<DebugSign>(entity) else {
       continue;
     };
+    if ds.infos == reflections {
+      continue;
+    }
+    ds.infos = reflections;
 
-    for reflection in reflections {
-      ds.infos.push(reflection);
+    // the sign's contents changed, so it needs to be redrawn
+    if let Some(mut shape) = world.get_mut::<RenderedShape>(entity) {
+      shape.invalidate();
     }
   }
 }
 
+/// Redraws every sign when the theme changes, since their colors come from it.
+fn invalidate_signs_on_theme_change(
+  theme: Res<Theme>,
+  mut query: Query<&mut RenderedShape, With<DebugSign>>,
+) {
+  if !theme.is_changed() {
+    return;
+  }
+  for mut shape in query.iter_mut() {
+    shape.invalidate();
+  }
+}
+
 fn render_signs(
   canvas_args: CanvasArgs,
+  theme: Res<Theme>,
   mut query: Query<(&DebugSign, &Transform, &mut RenderedShape)>,
 ) {
   use crate::shapes::*;
 
   for (ds, transform, mut buffer) in query.iter_mut() {
+    let Some(buffer) = buffer.redraw_buffer() else {
+      continue;
+    };
+
     let sign = SignArgs {
-      content:    ds.render(),
+      content:    ds.render(&theme),
       min_width:  Some(32),
       max_width:  40,
       max_height: None,
@@ -83,15 +101,17 @@ fn render_signs(
       on_top:     true,
     };
 
-    sign.draw(buffer.inner_mut(), &canvas_args, transform);
+    sign.draw(buffer, &canvas_args, transform);
   }
 }
 
 impl Plugin for DebugSignPlugin {
   fn build(&self, app: &mut App) {
     app
-      .add_systems(PreUpdate, clear_infos)
-      .add_systems(PostUpdate, propagate_infos)
+      .add_systems(
+        PostUpdate,
+        (propagate_infos, invalidate_signs_on_theme_change),
+      )
       .add_systems(Render, render_signs);
   }
 }
@@ -0,0 +1,354 @@
+//! Fading things out with depth, like fog.
+//!
+//! Each camera's [`DepthCue`] fades cells toward a color by how deep they are
+//! within what's visible to it. Depths are measured against the nearest and
+//! farthest cells the camera drew that frame rather than its whole depth
+//! range, so a zoomed-in view still fades from front to back.
+
+use bevy::prelude::*;
+use ratatui::{
+  buffer::Cell,
+  style::{Color, Modifier},
+};
+
+use crate::{
+  color_depth::{ColorDepth, blend_color},
+  shapes::DrawnMaterial,
+  theme::Theme,
+};
+
+/// How a camera fades what it draws with depth.
+#[derive(Component, Reflect, Clone, Debug)]
+pub struct DepthCue {
+  /// Where the fade starts, as a fraction of the visible depth range: 0 at
+  /// the nearest visible cell and 1 at the farthest.
+  pub near:      f32,
+  /// Where the fade reaches its full `strength`, as a fraction of the
+  /// visible depth range.
+  pub far:       f32,
+  pub curve:     DepthCueCurve,
+  /// How far toward `color` cells are faded at `far`, from 0 for not at all
+  /// to 1 for all the way.
+  pub strength:  f32,
+  /// The color cells fade toward, or the theme's base color if `None`.
+  #[reflect(ignore)]
+  pub color:     Option<Color>,
+  pub materials: DepthCueMaterials,
+}
+
+impl Default for DepthCue {
+  fn default() -> Self {
+    DepthCue {
+      near:      0.0,
+      far:       1.0,
+      curve:     DepthCueCurve::Linear,
+      strength:  0.6,
+      color:     None,
+      materials: DepthCueMaterials::Lineart,
+    }
+  }
+}
+
+impl DepthCue {
+  /// A depth cue that doesn't fade anything.
+  pub fn disabled() -> Self {
+    DepthCue {
+      strength: 0.0,
+      ..default()
+    }
+  }
+}
+
+/// How a [`DepthCue`] ramps up between its near and far points.
+#[derive(Reflect, Clone, Copy, Debug, Default, PartialEq)]
+pub enum DepthCueCurve {
+  /// Evenly from near to far.
+  #[default]
+  Linear,
+  /// Quickly just past the near point, then more and more slowly, like fog.
+  /// Higher densities ramp up faster.
+  Exponential { density: f32 },
+}
+
+impl DepthCueCurve {
+  /// Maps how far along from near to far a cell is, from 0 to 1, to how
+  /// faded it is, from 0 to 1.
+  fn apply(self, t: f32) -> f32 {
+    match self {
+      DepthCueCurve::Linear => t,
+      DepthCueCurve::Exponential { density } if density > f32::EPSILON => {
+        // normalized so that the far point is still fully faded
+        (1.0 - (-density * t).exp()) / (1.0 - (-density).exp())
+      }
+      DepthCueCurve::Exponential { .. } => t,
+    }
+  }
+}
+
+/// Which materials a [`DepthCue`] fades.
+#[derive(Reflect, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DepthCueMaterials {
+  /// Only the materials that ask to be, like wall edges and corners. See
+  /// [`MaterialKind::is_depth_cued`](crate::shapes::MaterialKind::is_depth_cued).
+  #[default]
+  Lineart,
+  /// The symbols of every material except text.
+  Strokes,
+  /// Everything, including backgrounds and text.
+  All,
+}
+
+/// A [`DepthCue`] set up for a single frame of a camera.
+pub(crate) struct DepthCueFrame<'a> {
+  cue:         &'a DepthCue,
+  /// The depths of the nearest and farthest visible cells.
+  range:       (f32, f32),
+  target:      Color,
+  color_depth: ColorDepth,
+}
+
+impl<'a> DepthCueFrame<'a> {
+  pub fn new(cue: &'a DepthCue, range: (f32, f32), theme: &Theme) -> Self {
+    DepthCueFrame {
+      cue,
+      range,
+      target: cue.color.unwrap_or(theme.base),
+      color_depth: theme.color_depth,
+    }
+  }
+
+  /// How far toward the target color a cell at `depth` is faded.
+  fn amount(&self, depth: f32) -> f32 {
+    let (nearest, farthest) = self.range;
+    let span = farthest - nearest;
+    if span <= f32::EPSILON || self.cue.strength <= 0.0 {
+      return 0.0;
+    }
+
+    let visible_t = (depth - nearest) / span;
+    let t = if self.cue.far > self.cue.near {
+      (visible_t - self.cue.near) / (self.cue.far - self.cue.near)
+    } else if visible_t >= self.cue.near {
+      1.0
+    } else {
+      0.0
+    };
+    self.cue.curve.apply(t.clamp(0.0, 1.0)) * self.cue.strength
+  }
+
+  /// Fades a rendered cell of `drawn`, if the cue applies to its material.
+  ///
+  /// When the terminal can't show enough colors for a smooth fade, the faded
+  /// colors are kept from disappearing into the background, and cells that
+  /// are more than halfway faded are dimmed instead.
+  pub fn apply(&self, cell: &mut Cell, drawn: &DrawnMaterial) {
+    let applies = match self.cue.materials {
+      DepthCueMaterials::Lineart => drawn.material().kind().is_depth_cued(),
+      DepthCueMaterials::Strokes => !drawn.is_glyph() && !drawn.is_blank(),
+      DepthCueMaterials::All => true,
+    };
+    if !applies {
+      return;
+    }
+    let amount = self.amount(drawn.proj_depth());
+    if amount <= 0.0 {
+      return;
+    }
+
+    let fade = |color| blend_color(color, self.target, amount);
+    let fades_bg = self.cue.materials == DepthCueMaterials::All;
+    if self.color_depth.is_true_color() {
+      cell.fg = fade(cell.fg);
+      if fades_bg {
+        cell.bg = fade(cell.bg);
+      }
+      return;
+    }
+
+    let quantize = |color| self.color_depth.quantize(color);
+    if fades_bg {
+      cell.bg = quantize(fade(cell.bg));
+    }
+    let faded = quantize(fade(cell.fg));
+    cell.fg = if faded == quantize(cell.bg) {
+      quantize(cell.fg)
+    } else {
+      faded
+    };
+    if amount > self.cue.strength / 2.0 {
+      cell.modifier.insert(Modifier::DIM);
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::shapes::{Material, MaterialDrawRequest};
+
+  const FOG: Color = Color::Rgb(0, 0, 0);
+
+  fn cue(near: f32, far: f32, curve: DepthCueCurve) -> DepthCue {
+    DepthCue {
+      near,
+      far,
+      curve,
+      strength: 1.0,
+      color: Some(FOG),
+      ..default()
+    }
+  }
+
+  fn theme(color_depth: ColorDepth) -> Theme {
+    Theme {
+      color_depth,
+      ..default()
+    }
+  }
+
+  fn corner_at(depth: f32) -> DrawnMaterial {
+    Material::wall_corner()
+      .draw(MaterialDrawRequest::None, depth)
+      .unwrap()
+  }
+
+  fn assert_close(actual: f32, expected: f32) {
+    assert!(
+      (actual - expected).abs() < 1e-5,
+      "expected {expected}, got {actual}"
+    );
+  }
+
+  #[test]
+  fn linear_curve_is_even() {
+    for t in [0.0, 0.25, 0.5, 1.0] {
+      assert_close(DepthCueCurve::Linear.apply(t), t);
+    }
+  }
+
+  #[test]
+  fn exponential_curve_ramps_up_quickly() {
+    let curve = DepthCueCurve::Exponential { density: 3.0 };
+    assert_close(curve.apply(0.0), 0.0);
+    assert_close(curve.apply(1.0), 1.0);
+    assert!(curve.apply(0.25) > 0.5);
+    assert!(curve.apply(0.25) < curve.apply(0.5));
+    // without any density, it's linear
+    let flat = DepthCueCurve::Exponential { density: 0.0 };
+    assert_close(flat.apply(0.3), 0.3);
+  }
+
+  #[test]
+  fn fades_across_the_visible_range() {
+    let cue = cue(0.0, 1.0, DepthCueCurve::Linear);
+    let frame =
+      DepthCueFrame::new(&cue, (0.2, 0.6), &theme(ColorDepth::TrueColor));
+    assert_close(frame.amount(0.2), 0.0);
+    assert_close(frame.amount(0.4), 0.5);
+    assert_close(frame.amount(0.6), 1.0);
+  }
+
+  #[test]
+  fn clamps_outside_near_and_far() {
+    let cue = cue(0.25, 0.75, DepthCueCurve::Linear);
+    let frame =
+      DepthCueFrame::new(&cue, (0.0, 1.0), &theme(ColorDepth::TrueColor));
+    assert_close(frame.amount(0.0), 0.0);
+    assert_close(frame.amount(0.25), 0.0);
+    assert_close(frame.amount(0.5), 0.5);
+    assert_close(frame.amount(0.75), 1.0);
+    assert_close(frame.amount(1.0), 1.0);
+  }
+
+  #[test]
+  fn steps_when_near_and_far_meet() {
+    let cue = cue(0.5, 0.5, DepthCueCurve::Linear);
+    let frame =
+      DepthCueFrame::new(&cue, (0.0, 1.0), &theme(ColorDepth::TrueColor));
+    assert_close(frame.amount(0.49), 0.0);
+    assert_close(frame.amount(0.5), 1.0);
+  }
+
+  #[test]
+  fn scales_by_strength() {
+    let cue = DepthCue {
+      strength: 0.6,
+      ..cue(0.0, 1.0, DepthCueCurve::Linear)
+    };
+    let frame =
+      DepthCueFrame::new(&cue, (0.0, 1.0), &theme(ColorDepth::TrueColor));
+    assert_close(frame.amount(1.0), 0.6);
+    assert_close(frame.amount(0.5), 0.3);
+  }
+
+  #[test]
+  fn fades_relative_to_a_zoomed_in_range() {
+    // a zoomed in view only covers a sliver of the camera's depth range, but
+    // still fades fully from its front to its back
+    let cue = cue(0.0, 1.0, DepthCueCurve::Linear);
+    let frame = DepthCueFrame::new(
+      &cue,
+      (0.5, 0.507_812_5),
+      &theme(ColorDepth::TrueColor),
+    );
+    assert_close(frame.amount(0.5), 0.0);
+    assert_close(frame.amount(0.503_906_25), 0.5);
+    assert_close(frame.amount(0.507_812_5), 1.0);
+
+    // with nothing to measure against, nothing is faded
+    let flat =
+      DepthCueFrame::new(&cue, (0.5, 0.5), &theme(ColorDepth::TrueColor));
+    assert_close(flat.amount(0.5), 0.0);
+  }
+
+  #[test]
+  fn blends_colors_in_true_color() {
+    let cue = cue(0.0, 1.0, DepthCueCurve::Linear);
+    let frame =
+      DepthCueFrame::new(&cue, (0.0, 1.0), &theme(ColorDepth::TrueColor));
+    let mut cell = Cell::default();
+    cell.fg = Color::Rgb(200, 100, 50);
+
+    frame.apply(&mut cell, &corner_at(0.5));
+    assert_eq!(cell.fg, Color::Rgb(100, 50, 25));
+    assert!(!cell.modifier.contains(Modifier::DIM));
+  }
+
+  #[test]
+  fn only_fades_lineart_by_default() {
+    let cue = cue(0.0, 1.0, DepthCueCurve::Linear);
+    let frame =
+      DepthCueFrame::new(&cue, (0.0, 1.0), &theme(ColorDepth::TrueColor));
+    let point = Material::colored_point(Color::Rgb(200, 100, 50))
+      .draw(MaterialDrawRequest::None, 1.0)
+      .unwrap();
+    let mut cell = Cell::default();
+    cell.fg = Color::Rgb(200, 100, 50);
+
+    frame.apply(&mut cell, &point);
+    assert_eq!(cell.fg, Color::Rgb(200, 100, 50));
+  }
+
+  #[test]
+  fn dims_instead_at_reduced_color_depths() {
+    let cue = cue(0.0, 1.0, DepthCueCurve::Linear);
+    let frame =
+      DepthCueFrame::new(&cue, (0.0, 1.0), &theme(ColorDepth::Ansi16));
+    let mut cell = Cell::default();
+    cell.fg = Color::Rgb(250, 250, 250);
+    cell.bg = FOG;
+
+    // fully faded, the stroke would vanish into the background, so it keeps
+    // its own color and is dimmed
+    frame.apply(&mut cell, &corner_at(1.0));
+    assert_eq!(cell.fg, Color::White);
+    assert!(cell.modifier.contains(Modifier::DIM));
+
+    // slightly faded cells aren't dimmed
+    let mut cell = Cell::default();
+    cell.fg = Color::Rgb(250, 250, 250);
+    cell.bg = FOG;
+    frame.apply(&mut cell, &corner_at(0.2));
+    assert!(!cell.modifier.contains(Modifier::DIM));
+  }
+}
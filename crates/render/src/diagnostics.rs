@@ -2,5 +2,9 @@ use bevy::diagnostic::DiagnosticPath;
 
 pub const SHAPE_BUFFER_COUNT_DIAG_PATH: DiagnosticPath =
   DiagnosticPath::const_new("render/shape_buffer_count");
+pub const CULLED_ENTITY_COUNT_DIAG_PATH: DiagnosticPath =
+  DiagnosticPath::const_new("render/culled_entity_count");
 pub const DRAWN_CELL_COUNT_DIAG_PATH: DiagnosticPath =
   DiagnosticPath::const_new("render/drawn_cell_count");
+pub const REDRAWN_SHAPE_COUNT_DIAG_PATH: DiagnosticPath =
+  DiagnosticPath::const_new("render/redrawn_shape_count");
@@ -1,21 +1,39 @@
 use bevy::{ecs::system::SystemParam, prelude::*};
 use ratatui::prelude::Color;
 
-use crate::shapes::{CanvasArgs, ShapeBuffer};
+use crate::shapes::{CanvasArgs, DrawnShape, LineArgs, ShapeBuffer};
 
+/// The gizmos recorded this frame. They're kept in world space and drawn
+/// again for every camera, then cleared once all cameras have rendered.
 #[derive(Resource, Default)]
 pub struct GizmoBuffer {
+  lines:  Vec<LineArgs>,
   buffer: ShapeBuffer,
 }
 
 impl GizmoBuffer {
-  pub fn buffer_mut(&mut self) -> &mut ShapeBuffer { &mut self.buffer }
+  pub(crate) fn update_extent(&mut self, extent: UVec2) {
+    self.buffer.update_extent(extent);
+  }
+
+  /// Draws the recorded gizmos for the camera `args` points at.
+  pub(crate) fn draw(&mut self, args: &CanvasArgs) -> &ShapeBuffer {
+    self.buffer.clear();
+    for line in &self.lines {
+      line.draw(&mut self.buffer, args, &Transform::IDENTITY);
+    }
+    &self.buffer
+  }
+
+  pub(crate) fn clear(&mut self) { self.lines.clear(); }
 }
 
+/// Records gizmos to be drawn this frame by every camera. Gizmos are
+/// immediate, so they have to be recorded again each frame, from any schedule
+/// before [`Last`].
 #[derive(SystemParam)]
 pub struct Gizmos<'w> {
-  buffer:      ResMut<'w, GizmoBuffer>,
-  canvas_args: CanvasArgs<'w>,
+  buffer: ResMut<'w, GizmoBuffer>,
 }
 
 impl Gizmos<'_> {
@@ -30,9 +48,11 @@ impl Gizmos<'_> {
     const Z_COLOR: Color = Color::Rgb(0, 0, 255);
 
     let line_style = LineStyle {
-      material:     Material::ColoredEdge(DIM_X_COLOR),
-      cap_material: Some(Material::ColoredPoint(X_COLOR)),
+      material:     Material::colored_edge(DIM_X_COLOR),
+      cap_material: Some(Material::colored_point(X_COLOR)),
       variant:      LineVariant::Thin,
+      pattern:      LinePattern::Solid,
+      hidden:       None,
     };
 
     let mut line = LineArgs {
@@ -41,38 +61,28 @@ impl Gizmos<'_> {
       style: line_style,
     };
 
-    line.draw(
-      self.buffer.buffer_mut(),
-      &self.canvas_args,
-      &Transform::IDENTITY,
-    );
+    self.buffer.lines.push(line.clone());
 
-    line.style.material = Material::ColoredEdge(DIM_Y_COLOR);
-    line.style.cap_material = Some(Material::ColoredPoint(Y_COLOR));
+    line.style.material = Material::colored_edge(DIM_Y_COLOR);
+    line.style.cap_material = Some(Material::colored_point(Y_COLOR));
     line.to = pos + Vec3::Y * length;
-    line.draw(
-      self.buffer.buffer_mut(),
-      &self.canvas_args,
-      &Transform::IDENTITY,
-    );
+    self.buffer.lines.push(line.clone());
 
-    line.style.material = Material::ColoredEdge(DIM_Z_COLOR);
-    line.style.cap_material = Some(Material::ColoredPoint(Z_COLOR));
+    line.style.material = Material::colored_edge(DIM_Z_COLOR);
+    line.style.cap_material = Some(Material::colored_point(Z_COLOR));
     line.to = pos + Vec3::Z * length;
-    line.draw(
-      self.buffer.buffer_mut(),
-      &self.canvas_args,
-      &Transform::IDENTITY,
-    );
+    self.buffer.lines.push(line);
   }
 
   pub fn cornered_box_gizmo(&mut self, pos: Vec3, size: Vec3, color: Color) {
     use crate::shapes::*;
 
     let line_style = LineStyle {
-      material:     Material::ColoredEdge(color),
-      cap_material: Some(Material::ColoredPoint(color)),
+      material:     Material::colored_edge(color),
+      cap_material: Some(Material::colored_point(color)),
       variant:      LineVariant::Thin,
+      pattern:      LinePattern::Solid,
+      hidden:       None,
     };
 
     let axis_mask_iter =
@@ -92,25 +102,13 @@ impl Gizmos<'_> {
         style: line_style.clone(),
       };
 
-      line.draw(
-        self.buffer.buffer_mut(),
-        &self.canvas_args,
-        &Transform::IDENTITY,
-      );
+      self.buffer.lines.push(line.clone());
 
       line.to = to_y;
-      line.draw(
-        self.buffer.buffer_mut(),
-        &self.canvas_args,
-        &Transform::IDENTITY,
-      );
+      self.buffer.lines.push(line.clone());
 
       line.to = to_z;
-      line.draw(
-        self.buffer.buffer_mut(),
-        &self.canvas_args,
-        &Transform::IDENTITY,
-      );
+      self.buffer.lines.push(line);
     }
   }
 }
@@ -120,3 +118,60 @@ pub struct GizmoPlugin;
 impl Plugin for GizmoPlugin {
   fn build(&self, app: &mut App) { app.init_resource::<GizmoBuffer>(); }
 }
+
+#[cfg(test)]
+mod tests {
+  use ratatui::layout::Rect;
+
+  use super::*;
+  use crate::{
+    camera::Camera,
+    headless::{HeadlessRenderer, buffer_to_plain_text},
+    render_buffer::RenderBuffer,
+  };
+
+  #[test]
+  fn every_camera_draws_gizmos() {
+    let size = UVec2::new(24, 12);
+    let camera_transform =
+      Transform::from_xyz(0.0, 8.0, 14.0).looking_at(Vec3::ZERO, Vec3::Y);
+    let mut renderer = HeadlessRenderer::new(
+      size,
+      Camera::default().with_scale(0.1),
+      camera_transform,
+    );
+    let app = renderer.app_mut();
+    app.add_systems(Update, |mut gizmos: Gizmos| {
+      gizmos.axis_gizmo(Vec3::ZERO, 2.0);
+    });
+    let second_camera = app
+      .world_mut()
+      .spawn((Camera::default().with_scale(0.2), camera_transform))
+      .id();
+    *app
+      .world_mut()
+      .get_mut::<RenderBuffer>(second_camera)
+      .unwrap()
+      .widget_state_mut()
+      .last_area_mut() = Rect::new(0, 0, size.x as _, size.y as _);
+
+    // the first frame picks up the second camera's render area
+    renderer.render_frame();
+    let first = buffer_to_plain_text(&renderer.render_frame());
+    let second = buffer_to_plain_text(
+      renderer
+        .app_mut()
+        .world()
+        .get::<RenderBuffer>(second_camera)
+        .unwrap()
+        .widget_state()
+        .buffer(),
+    );
+
+    let is_blank = |text: &str| text.chars().all(char::is_whitespace);
+    assert!(!is_blank(&first));
+    assert!(!is_blank(&second));
+    // the cameras are zoomed differently, so each projected the gizmo itself
+    assert_ne!(first, second);
+  }
+}
@@ -0,0 +1,257 @@
+//! A headless harness for rendering frames without a terminal.
+//!
+//! This builds a minimal [`App`] with the [`RenderPlugin`], a fixed render
+//! size, and a single [`MainCamera`], and lets you pull the rendered
+//! [`Buffer`] out after a frame. Paired with [`assert_snapshot`], it's meant
+//! for locking down what the renderer actually draws.
+
+use std::{fmt::Write as _, fs, path::Path};
+
+use bevy::prelude::*;
+use ratatui::{buffer::Buffer, layout::Rect, style::Color};
+
+use crate::{
+  Render, RenderPlugin,
+  camera::{Camera, MainCamera},
+  color_depth::ColorDepth,
+  culling::ShapeVisibility,
+  render_buffer::RenderBuffer,
+  shapes::{CanvasArgs, DrawnShape, RenderedShape},
+  theme::Theme,
+};
+
+/// The environment variable that causes [`assert_snapshot`] to overwrite
+/// snapshots instead of comparing against them.
+pub const UPDATE_SNAPSHOTS_ENV_VAR: &str = "UPDATE_SNAPSHOTS";
+
+/// A type-erased shape drawn by the headless harness.
+#[derive(Component)]
+#[require(RenderedShape, Transform)]
+struct HeadlessShape(Box<dyn DrawnShape + Send + Sync>);
+
+fn render_headless_shapes(
+  canvas_args: CanvasArgs,
+  mut query: Query<(
+    &HeadlessShape,
+    &Transform,
+    &ShapeVisibility,
+    &mut RenderedShape,
+  )>,
+) {
+  for (shape, transform, visibility, mut buffer) in query.iter_mut() {
+    if !visibility.is_visible() {
+      continue;
+    }
+    let Some(buffer) = buffer.redraw_buffer() else {
+      continue;
+    };
+    shape.0.draw(buffer, &canvas_args, transform);
+  }
+}
+
+/// Renders frames into an in-memory buffer of a fixed size.
+pub struct HeadlessRenderer {
+  app:    App,
+  camera: Entity,
+}
+
+impl HeadlessRenderer {
+  /// Creates a new [`HeadlessRenderer`] with a render area of `size` cells,
+  /// viewed through `camera` at `camera_transform`.
+  pub fn new(size: UVec2, camera: Camera, camera_transform: Transform) -> Self {
+    let mut app = App::new();
+    app
+      .add_plugins((MinimalPlugins, RenderPlugin))
+      .add_systems(Render, render_headless_shapes);
+
+    app.finish();
+    app.cleanup();
+
+    // keep renders the same whatever terminal the tests are run in
+    app.world_mut().resource_mut::<Theme>().color_depth = ColorDepth::TrueColor;
+
+    let camera = app
+      .world_mut()
+      .spawn((camera, camera_transform, MainCamera))
+      .id();
+
+    // the render area is normally synced from the widget during the UI pass
+    *app
+      .world_mut()
+      .get_mut::<RenderBuffer>(camera)
+      .unwrap()
+      .widget_state_mut()
+      .last_area_mut() = Rect::new(0, 0, size.x as _, size.y as _);
+
+    Self { app, camera }
+  }
+
+  /// Gives access to the underlying [`App`], for adding plugins, systems or
+  /// entities.
+  pub fn app_mut(&mut self) -> &mut App { &mut self.app }
+
+  /// Spawns an entity that draws `shape` with `transform`.
+  pub fn spawn_shape(
+    &mut self,
+    shape: impl DrawnShape + Send + Sync + 'static,
+    transform: Transform,
+  ) -> Entity {
+    let bounds = shape.bounds();
+    let mut entity = self
+      .app
+      .world_mut()
+      .spawn((HeadlessShape(Box::new(shape)), transform));
+    if let Some(bounds) = bounds {
+      entity.insert(bounds);
+    }
+    entity.id()
+  }
+
+  /// The entity of the harness's camera.
+  pub fn camera(&self) -> Entity { self.camera }
+
+  /// Runs a single frame and returns the rendered buffer.
+  pub fn render_frame(&mut self) -> Buffer {
+    self.app.update();
+    self
+      .app
+      .world()
+      .get::<RenderBuffer>(self.camera)
+      .unwrap()
+      .widget_state()
+      .buffer()
+      .clone()
+  }
+}
+
+/// Converts a buffer to plain text, one line per row.
+pub fn buffer_to_plain_text(buffer: &Buffer) -> String {
+  let mut output = String::new();
+
+  for y in 0..buffer.area.height {
+    for x in 0..buffer.area.width {
+      let cell = &buffer[(buffer.area.x + x, buffer.area.y + y)];
+      if !cell.skip {
+        output.push_str(cell.symbol());
+      }
+    }
+    output.push('\n');
+  }
+
+  output
+}
+
+/// Converts a buffer to text with ANSI SGR escape codes for its colors.
+pub fn buffer_to_ansi(buffer: &Buffer) -> String {
+  let mut output = String::new();
+
+  for y in 0..buffer.area.height {
+    let mut last_colors = None;
+    for x in 0..buffer.area.width {
+      let cell = &buffer[(buffer.area.x + x, buffer.area.y + y)];
+      if cell.skip {
+        continue;
+      }
+
+      if last_colors != Some((cell.fg, cell.bg)) {
+        output.push_str("\x1b[0");
+        push_sgr_color(&mut output, cell.fg, false);
+        push_sgr_color(&mut output, cell.bg, true);
+        output.push('m');
+        last_colors = Some((cell.fg, cell.bg));
+      }
+      output.push_str(cell.symbol());
+    }
+    output.push_str("\x1b[0m\n");
+  }
+
+  output
+}
+
+fn push_sgr_color(output: &mut String, color: Color, background: bool) {
+  let base = if background { 40 } else { 30 };
+  let bright_base = if background { 100 } else { 90 };
+
+  // writing to a `String` can't fail
+  let _ = match color {
+    Color::Reset => Ok(()),
+    Color::Black => write!(output, ";{}", base),
+    Color::Red => write!(output, ";{}", base + 1),
+    Color::Green => write!(output, ";{}", base + 2),
+    Color::Yellow => write!(output, ";{}", base + 3),
+    Color::Blue => write!(output, ";{}", base + 4),
+    Color::Magenta => write!(output, ";{}", base + 5),
+    Color::Cyan => write!(output, ";{}", base + 6),
+    Color::Gray => write!(output, ";{}", base + 7),
+    Color::DarkGray => write!(output, ";{}", bright_base),
+    Color::LightRed => write!(output, ";{}", bright_base + 1),
+    Color::LightGreen => write!(output, ";{}", bright_base + 2),
+    Color::LightYellow => write!(output, ";{}", bright_base + 3),
+    Color::LightBlue => write!(output, ";{}", bright_base + 4),
+    Color::LightMagenta => write!(output, ";{}", bright_base + 5),
+    Color::LightCyan => write!(output, ";{}", bright_base + 6),
+    Color::White => write!(output, ";{}", bright_base + 7),
+    Color::Rgb(r, g, b) => write!(output, ";{};2;{r};{g};{b}", base + 8),
+    Color::Indexed(i) => write!(output, ";{};5;{i}", base + 8),
+  };
+}
+
+/// Compares `actual` against the snapshot stored at `path`.
+///
+/// If the [`UPDATE_SNAPSHOTS_ENV_VAR`] environment variable is set, the
+/// snapshot is written instead, including new ones.
+///
+/// # Panics
+/// Panics if the snapshot differs from `actual` or doesn't exist, or if the
+/// snapshot file can't be read or written.
+pub fn assert_snapshot(path: impl AsRef<Path>, actual: &str) {
+  let path = path.as_ref();
+
+  if std::env::var_os(UPDATE_SNAPSHOTS_ENV_VAR).is_some() {
+    if let Some(parent) = path.parent() {
+      fs::create_dir_all(parent).unwrap_or_else(|e| {
+        panic!("failed to create snapshot dir {}: {e}", parent.display())
+      });
+    }
+    fs::write(path, actual).unwrap_or_else(|e| {
+      panic!("failed to write snapshot {}: {e}", path.display())
+    });
+    return;
+  }
+
+  // a missing snapshot has to be added on purpose, or it'd pass anywhere the
+  // snapshots weren't checked in
+  if !path.exists() {
+    panic!(
+      "snapshot {} doesn't exist (set `{UPDATE_SNAPSHOTS_ENV_VAR}` to create \
+       it)",
+      path.display()
+    );
+  }
+
+  let expected = fs::read_to_string(path).unwrap_or_else(|e| {
+    panic!("failed to read snapshot {}: {e}", path.display())
+  });
+  if expected == actual {
+    return;
+  }
+
+  let mut diff = String::new();
+  let expected_lines = expected.lines().collect::<Vec<_>>();
+  let actual_lines = actual.lines().collect::<Vec<_>>();
+  for i in 0..expected_lines.len().max(actual_lines.len()) {
+    let expected_line = expected_lines.get(i).copied().unwrap_or_default();
+    let actual_line = actual_lines.get(i).copied().unwrap_or_default();
+    if expected_line != actual_line {
+      let _ = writeln!(diff, "line {i}:");
+      let _ = writeln!(diff, "  - {expected_line}");
+      let _ = writeln!(diff, "  + {actual_line}");
+    }
+  }
+
+  panic!(
+    "snapshot {} does not match (set `{UPDATE_SNAPSHOTS_ENV_VAR}` to \
+     update):\n{diff}",
+    path.display()
+  );
+}
@@ -1,67 +1,182 @@
 pub mod camera;
+pub mod color_depth;
+pub mod culling;
 pub mod debug_signage;
+pub mod depth_cue;
 pub mod diagnostics;
 pub mod gizmo;
+#[cfg(any(test, feature = "headless"))]
+pub mod headless;
+pub mod lighting;
+pub mod obj;
 pub mod render_buffer;
 pub mod shapes;
+#[cfg(test)]
+mod snapshot_tests;
+pub mod theme;
 
 use bevy::{
-  app::MainScheduleOrder,
   diagnostic::{Diagnostic, Diagnostics, RegisterDiagnostic},
-  ecs::schedule::ScheduleLabel,
+  ecs::{schedule::ScheduleLabel, system::SystemChangeTick},
   prelude::*,
 };
-use colors::{BASE_COLOR_RATATUI, PUNCHY_TEXT_COLOR_RATATUI};
-use ratatui::buffer::Cell;
 
 use self::{
   camera::{
-    Camera, CameraMatrix, MainCamera, MainCameraMatrix, update_camera_matrices,
+    ActiveCameraMatrix, Camera, CameraMatrix, MainCamera, MainCameraMatrix,
+    update_camera_matrices,
   },
+  culling::{ShapeBounds, ShapeVisibility, cull_shapes},
   debug_signage::DebugSignPlugin,
-  diagnostics::{DRAWN_CELL_COUNT_DIAG_PATH, SHAPE_BUFFER_COUNT_DIAG_PATH},
+  depth_cue::DepthCue,
+  diagnostics::{
+    CULLED_ENTITY_COUNT_DIAG_PATH, DRAWN_CELL_COUNT_DIAG_PATH,
+    REDRAWN_SHAPE_COUNT_DIAG_PATH, SHAPE_BUFFER_COUNT_DIAG_PATH,
+  },
   gizmo::{GizmoBuffer, GizmoPlugin},
-  render_buffer::{RenderBuffer, RenderBufferSize, prepare_for_frame},
-  shapes::{RenderedShape, ShapeBuffer},
+  lighting::{
+    AmbientLight, DirectionalLight, PointLight, SceneLights, collect_lights,
+  },
+  render_buffer::{
+    RenderBuffer, RenderBufferSize, prepare_for_frame, prepare_shape_buffers,
+  },
+  shapes::{
+    CanvasArgs, DepthBuffer, MaterialRegistry, RenderedShape,
+    forget_removed_cameras,
+  },
+  theme::Theme,
 };
 
-const DEFAULT_CELL: Cell = const {
-  let mut cell = Cell::EMPTY;
-  cell.bg = BASE_COLOR_RATATUI;
-  cell.fg = PUNCHY_TEXT_COLOR_RATATUI;
-  cell
-};
 const MAX_PROJECTED_DEPTH: f32 = 1000.0;
 
-pub fn render_shape_buffers(
-  mut render_buffer: ResMut<RenderBuffer>,
-  mut query: Query<&mut shapes::RenderedShape>,
+/// Runs the [`Render`] schedule once for every camera, compositing the drawn
+/// shapes into each camera's own [`RenderBuffer`].
+pub fn render_cameras(world: &mut World) {
+  let mut camera_query = world
+    .query_filtered::<(Entity, &CameraMatrix, &RenderBufferSize), With<Camera>>(
+    );
+  let cameras = camera_query
+    .iter(world)
+    .map(|(entity, matrix, size)| (entity, matrix.clone(), size.clone()))
+    .collect::<Vec<_>>();
+
+  let mut stats = RenderStats::default();
+
+  for (camera, camera_matrix, render_buffer_size) in cameras {
+    // point `CanvasArgs` at this camera
+    world.insert_resource(ActiveCameraMatrix(camera_matrix));
+    world.insert_resource(render_buffer_size);
+
+    let _ = world.run_system_cached_with(prepare_shape_buffers, camera);
+    stats.culled_entities += world.run_system_cached(cull_shapes).unwrap_or(0);
+    world.run_schedule(Render);
+
+    if let Ok(camera_stats) =
+      world.run_system_cached_with(render_shape_buffers, camera)
+    {
+      stats += camera_stats;
+    }
+  }
+
+  // every camera has drawn this frame's gizmos now
+  world.resource_mut::<GizmoBuffer>().clear();
+
+  let _ = world.run_system_cached_with(record_render_diagnostics, stats);
+}
+
+/// Totals of what was rendered across all cameras in a frame.
+#[derive(Default)]
+struct RenderStats {
+  shape_buffers:   usize,
+  drawn_cells:     usize,
+  culled_entities: usize,
+  redrawn_shapes:  usize,
+}
+
+impl std::ops::AddAssign for RenderStats {
+  fn add_assign(&mut self, rhs: Self) {
+    self.shape_buffers += rhs.shape_buffers;
+    self.drawn_cells += rhs.drawn_cells;
+    self.culled_entities += rhs.culled_entities;
+    self.redrawn_shapes += rhs.redrawn_shapes;
+  }
+}
+
+/// Composites every visible shape buffer into the given camera's
+/// [`RenderBuffer`].
+#[allow(clippy::too_many_arguments)]
+fn render_shape_buffers(
+  In(camera): In<Entity>,
+  mut depth_buffer: Local<DepthBuffer>,
+  render_buffer_size: Res<RenderBufferSize>,
+  mut render_buffers: Query<(&mut RenderBuffer, &CameraMatrix, &DepthCue)>,
+  mut query: Query<(&mut RenderedShape, &ShapeVisibility)>,
   mut gizmo_buffer: ResMut<GizmoBuffer>,
+  canvas_args: CanvasArgs,
+  theme: Res<Theme>,
+  ticks: SystemChangeTick,
+) -> RenderStats {
+  let mut stats = RenderStats::default();
+  let Ok((mut render_buffer, camera_matrix, depth_cue)) =
+    render_buffers.get_mut(camera)
+  else {
+    return stats;
+  };
+
+  let resolution = camera_matrix.resolution();
+  depth_buffer.reset(
+    render_buffer_size.size() * resolution.points_per_cell(),
+    resolution,
+  );
+
+  for (mut shape, visibility) in query.iter_mut() {
+    // culled shapes weren't redrawn, so their buffers are stale
+    if !visibility.is_visible() {
+      continue;
+    }
+    if shape.mark_drawn(ticks.this_run()) {
+      stats.redrawn_shapes += 1;
+    }
+    let Some(shape_buffer) = shape.active_buffer() else {
+      continue;
+    };
+    stats.shape_buffers += 1;
+    stats.drawn_cells += shape_buffer.len();
+    depth_buffer.composite(shape_buffer);
+  }
+
+  // gizmos are immediate, so they're drawn fresh for every camera
+  let gizmo_buffer = gizmo_buffer.draw(&canvas_args);
+  stats.shape_buffers += 1;
+  stats.drawn_cells += gizmo_buffer.len();
+  depth_buffer.composite(gizmo_buffer);
+
+  let buffer = render_buffer.widget_state_mut().buffer_mut();
+  depth_buffer.render(buffer, &theme, depth_cue);
+  theme.color_depth.quantize_buffer(buffer);
+
+  stats
+}
+
+fn record_render_diagnostics(
+  In(stats): In<RenderStats>,
   mut diagnostics: Diagnostics,
 ) {
-  let mut buffer_count = 0;
-  let buffer_iter = query
-    .iter_mut()
-    .map(|b| b.into_inner().inner_mut())
-    .chain(Some(gizmo_buffer.buffer_mut()))
-    .inspect(|_| buffer_count += 1);
-
-  let master_shape_buffer = ShapeBuffer::merge(buffer_iter);
+  diagnostics.add_measurement(&SHAPE_BUFFER_COUNT_DIAG_PATH, || {
+    stats.shape_buffers as f64
+  });
+  diagnostics.add_measurement(&CULLED_ENTITY_COUNT_DIAG_PATH, || {
+    stats.culled_entities as f64
+  });
   diagnostics
-    .add_measurement(&SHAPE_BUFFER_COUNT_DIAG_PATH, || buffer_count as f64);
-  diagnostics.add_measurement(&DRAWN_CELL_COUNT_DIAG_PATH, || {
-    master_shape_buffer.len() as f64
+    .add_measurement(&DRAWN_CELL_COUNT_DIAG_PATH, || stats.drawn_cells as f64);
+  diagnostics.add_measurement(&REDRAWN_SHAPE_COUNT_DIAG_PATH, || {
+    stats.redrawn_shapes as f64
   });
-
-  let truncated_master = master_shape_buffer.truncate();
-  let rendered_master = truncated_master.render(render_buffer.render_area());
-
-  render_buffer
-    .widget_state_mut()
-    .buffer_mut()
-    .merge(&rendered_master);
 }
 
+/// The schedule that shapes are drawn in. It runs once per camera, with
+/// [`CanvasArgs`](shapes::CanvasArgs) pointed at that camera.
 #[derive(ScheduleLabel, Debug, Clone, PartialEq, Eq, Hash)]
 pub struct Render;
 
@@ -70,24 +185,32 @@ pub struct RenderPlugin;
 impl Plugin for RenderPlugin {
   fn build(&self, app: &mut App) {
     app.init_schedule(Render);
-    app
-      .world_mut()
-      .resource_mut::<MainScheduleOrder>()
-      .insert_after(PostUpdate, Render);
 
     app
-      .init_resource::<RenderBuffer>()
       .init_resource::<RenderBufferSize>()
       .init_resource::<MainCameraMatrix>()
+      .init_resource::<ActiveCameraMatrix>()
+      .init_resource::<MaterialRegistry>()
+      .init_resource::<Theme>()
+      .init_resource::<AmbientLight>()
+      .init_resource::<SceneLights>()
       .register_type::<Camera>()
       .register_type::<CameraMatrix>()
+      .register_type::<DepthCue>()
+      .register_type::<DirectionalLight>()
+      .register_type::<PointLight>()
+      .register_type::<AmbientLight>()
       .register_type::<MainCamera>()
       .register_type::<RenderedShape>()
+      .register_type::<ShapeBounds>()
+      .register_type::<ShapeVisibility>()
       .register_diagnostic(Diagnostic::new(SHAPE_BUFFER_COUNT_DIAG_PATH))
+      .register_diagnostic(Diagnostic::new(CULLED_ENTITY_COUNT_DIAG_PATH))
       .register_diagnostic(Diagnostic::new(DRAWN_CELL_COUNT_DIAG_PATH))
+      .register_diagnostic(Diagnostic::new(REDRAWN_SHAPE_COUNT_DIAG_PATH))
       .add_systems(PreUpdate, prepare_for_frame)
-      .add_systems(PostUpdate, update_camera_matrices)
-      .add_systems(Last, render_shape_buffers);
+      .add_systems(PostUpdate, (update_camera_matrices, collect_lights))
+      .add_systems(Last, (forget_removed_cameras, render_cameras).chain());
 
     app.add_plugins((GizmoPlugin, DebugSignPlugin));
   }
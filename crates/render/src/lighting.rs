@@ -0,0 +1,126 @@
+//! Lights that faces are shaded by.
+//!
+//! Lights are components on entities, placed and aimed by their
+//! [`Transform`]. Every frame they're gathered into [`SceneLights`], which
+//! shapes read through [`CanvasArgs`](crate::shapes::CanvasArgs) to work out
+//! how brightly each of their faces is lit. Faces are only shaded if their
+//! style asks for it, with a [`FaceShading`](crate::shapes::FaceShading).
+
+use bevy::prelude::*;
+
+/// A light shining from infinitely far away, like a sun, along the forward
+/// (`-Z`) direction of its entity.
+#[derive(Component, Reflect, Clone, Copy, Debug)]
+#[require(Transform)]
+pub struct DirectionalLight {
+  pub intensity: f32,
+}
+
+impl Default for DirectionalLight {
+  fn default() -> Self { DirectionalLight { intensity: 1.0 } }
+}
+
+/// A light shining in every direction from its entity's position, like a
+/// lamp. It fades out smoothly to nothing at `range`.
+#[derive(Component, Reflect, Clone, Copy, Debug)]
+#[require(Transform)]
+pub struct PointLight {
+  pub intensity: f32,
+  pub range:     f32,
+}
+
+impl Default for PointLight {
+  fn default() -> Self {
+    PointLight {
+      intensity: 1.0,
+      range:     10.0,
+    }
+  }
+}
+
+/// The light every face gets, whichever way it's facing.
+#[derive(Resource, Reflect, Clone, Copy, Debug)]
+pub struct AmbientLight {
+  pub intensity: f32,
+}
+
+impl Default for AmbientLight {
+  fn default() -> Self { AmbientLight { intensity: 0.1 } }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct SceneDirectionalLight {
+  /// The direction the light travels in.
+  direction: Vec3,
+  intensity: f32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct ScenePointLight {
+  position:  Vec3,
+  intensity: f32,
+  range:     f32,
+}
+
+/// Every light in the scene, gathered from their entities each frame.
+///
+/// Shapes are redrawn whenever this changes, since their shading might have.
+#[derive(Resource, Clone, Debug, Default, PartialEq)]
+pub struct SceneLights {
+  ambient:     f32,
+  directional: Vec<SceneDirectionalLight>,
+  point:       Vec<ScenePointLight>,
+}
+
+impl SceneLights {
+  /// How brightly a surface at `point` facing along `normal` is lit, from 0
+  /// for unlit to 1 for fully lit. Both are in world space.
+  pub fn intensity(&self, point: Vec3, normal: Vec3) -> f32 {
+    let normal = normal.normalize_or_zero();
+
+    let directional = self
+      .directional
+      .iter()
+      .map(|light| normal.dot(-light.direction).max(0.0) * light.intensity);
+    let point = self.point.iter().map(|light| {
+      let to_light = light.position - point;
+      let distance = to_light.length();
+      if distance >= light.range || distance <= f32::EPSILON {
+        return 0.0;
+      }
+      let falloff = (1.0 - distance / light.range).powi(2);
+      normal.dot(to_light / distance).max(0.0) * light.intensity * falloff
+    });
+
+    (self.ambient + directional.chain(point).sum::<f32>()).clamp(0.0, 1.0)
+  }
+}
+
+pub(crate) fn collect_lights(
+  ambient: Res<AmbientLight>,
+  directional_query: Query<(&DirectionalLight, &Transform)>,
+  point_query: Query<(&PointLight, &Transform)>,
+  mut scene_lights: ResMut<SceneLights>,
+) {
+  let lights = SceneLights {
+    ambient:     ambient.intensity,
+    directional: directional_query
+      .iter()
+      .map(|(light, transform)| SceneDirectionalLight {
+        direction: *transform.forward(),
+        intensity: light.intensity,
+      })
+      .collect(),
+    point:       point_query
+      .iter()
+      .map(|(light, transform)| ScenePointLight {
+        position:  transform.translation,
+        intensity: light.intensity,
+        range:     light.range,
+      })
+      .collect(),
+  };
+
+  // only mark it changed when it is, since that redraws every shape
+  scene_lights.set_if_neq(lights);
+}
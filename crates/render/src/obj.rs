@@ -0,0 +1,425 @@
+//! Loading Wavefront OBJ models as drawable meshes.
+//!
+//! [`ObjPlugin`] registers an asset loader for `.obj` files, which produces
+//! an [`ObjModel`]. Spawning an [`ObjShape`] with a handle to one draws it with
+//! the given [`MeshStyle`], either as a wireframe of its feature edges, as
+//! filled faces, or both.
+//!
+//! Only vertex positions and faces are read. Texture coordinates, normals,
+//! groups and materials are ignored.
+
+use std::{fmt, str::FromStr};
+
+use bevy::{
+  asset::{AssetLoader, LoadContext, io::Reader},
+  prelude::*,
+  utils::HashMap,
+};
+
+use crate::{
+  Render,
+  culling::{ShapeBounds, ShapeVisibility},
+  shapes::{
+    CanvasArgs, MeshStyle, RenderedShape, detect_feature_edges, draw_mesh,
+  },
+};
+
+/// Faces meeting at more than this angle (in radians) have the edge between
+/// them drawn.
+pub const DEFAULT_FEATURE_ANGLE: f32 = std::f32::consts::PI / 6.0;
+
+/// A triangulated model loaded from an OBJ file.
+#[derive(Asset, TypePath, Clone, Debug, Default)]
+pub struct ObjModel {
+  /// The model's vertex positions, with duplicates merged.
+  pub vertices:      Vec<Vec3>,
+  /// Triangles, as indices into `vertices`.
+  pub triangles:     Vec<[usize; 3]>,
+  /// The edges worth drawing as lines, as pairs of indices into `vertices`.
+  pub feature_edges: Vec<(usize, usize)>,
+}
+
+impl ObjModel {
+  /// Parses the contents of an OBJ file, detecting feature edges at
+  /// [`DEFAULT_FEATURE_ANGLE`].
+  pub fn parse(source: &str) -> Result<Self, ObjLoadError> {
+    let mut positions = Vec::new();
+    let mut faces = Vec::new();
+
+    for (line_index, line) in source.lines().enumerate() {
+      let line_number = line_index + 1;
+      let error = |message: String| ObjLoadError::Parse {
+        line: line_number,
+        message,
+      };
+
+      let mut tokens = line.split('#').next().unwrap().split_whitespace();
+      match tokens.next() {
+        Some("v") => {
+          let mut coords = tokens.map(parse_token::<f32>);
+          let mut coord = || {
+            coords
+              .next()
+              .unwrap_or_else(|| Err("missing coordinate".to_string()))
+              .map_err(error)
+          };
+          positions.push(Vec3::new(coord()?, coord()?, coord()?));
+        }
+        Some("f") => {
+          let face = tokens
+            .map(|token| {
+              // faces can be `v`, `v/vt`, `v//vn` or `v/vt/vn`
+              let index =
+                parse_token::<isize>(token.split('/').next().unwrap())
+                  .map_err(error)?;
+              resolve_index(index, positions.len()).ok_or_else(|| {
+                error(format!("vertex index {index} is out of range"))
+              })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+          if face.len() < 3 {
+            return Err(error("face has fewer than 3 vertices".to_string()));
+          }
+          faces.push(face);
+        }
+        // anything else doesn't affect the shape
+        _ => {}
+      }
+    }
+
+    // OBJ exporters split vertices along UV and normal seams, which would
+    // make every seam look like a border, so merge them by position
+    let mut vertices = Vec::new();
+    let mut merged_indices = HashMap::new();
+    let remap = positions
+      .iter()
+      .map(|position: &Vec3| {
+        *merged_indices
+          .entry(position.to_array().map(f32::to_bits))
+          .or_insert_with(|| {
+            vertices.push(*position);
+            vertices.len() - 1
+          })
+      })
+      .collect::<Vec<_>>();
+
+    // faces are convex polygons, so they can be drawn as fans
+    let triangles = faces
+      .iter()
+      .flat_map(|face| {
+        face[1..]
+          .windows(2)
+          .map(|pair| [face[0], pair[0], pair[1]].map(|i| remap[i]))
+      })
+      .collect::<Vec<_>>();
+
+    let feature_edges =
+      detect_feature_edges(&vertices, &triangles, DEFAULT_FEATURE_ANGLE);
+
+    Ok(ObjModel {
+      vertices,
+      triangles,
+      feature_edges,
+    })
+  }
+
+  /// The model's local-space bounds.
+  pub fn bounds(&self) -> ShapeBounds {
+    ShapeBounds::from_points(self.vertices.iter().copied())
+  }
+}
+
+fn parse_token<T: FromStr>(token: &str) -> Result<T, String> {
+  token
+    .parse()
+    .map_err(|_| format!("failed to parse `{token}` as a number"))
+}
+
+/// Turns a 1-based OBJ index, which counts back from the end if it's
+/// negative, into an index into the vertices parsed so far.
+fn resolve_index(index: isize, vertex_count: usize) -> Option<usize> {
+  let resolved = match index {
+    0 => return None,
+    1.. => index as usize - 1,
+    ..0 => vertex_count.checked_sub(index.unsigned_abs())?,
+  };
+  (resolved < vertex_count).then_some(resolved)
+}
+
+/// An error from loading an OBJ file.
+#[derive(Debug)]
+pub enum ObjLoadError {
+  /// The file couldn't be read.
+  Io(std::io::Error),
+  /// The file isn't valid UTF-8.
+  Utf8(std::str::Utf8Error),
+  /// A line of the file couldn't be parsed.
+  Parse { line: usize, message: String },
+}
+
+impl fmt::Display for ObjLoadError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      ObjLoadError::Io(e) => write!(f, "failed to read OBJ file: {e}"),
+      ObjLoadError::Utf8(e) => write!(f, "OBJ file is not valid UTF-8: {e}"),
+      ObjLoadError::Parse { line, message } => {
+        write!(f, "failed to parse OBJ file on line {line}: {message}")
+      }
+    }
+  }
+}
+
+impl std::error::Error for ObjLoadError {}
+
+impl From<std::io::Error> for ObjLoadError {
+  fn from(e: std::io::Error) -> Self { ObjLoadError::Io(e) }
+}
+
+/// Loads `.obj` files as [`ObjModel`]s.
+#[derive(Default)]
+pub struct ObjLoader;
+
+impl AssetLoader for ObjLoader {
+  type Asset = ObjModel;
+  type Settings = ();
+  type Error = ObjLoadError;
+
+  async fn load(
+    &self,
+    reader: &mut dyn Reader,
+    _settings: &(),
+    _load_context: &mut LoadContext<'_>,
+  ) -> Result<ObjModel, ObjLoadError> {
+    let mut bytes = Vec::new();
+    reader.read_to_end(&mut bytes).await?;
+    let source = std::str::from_utf8(&bytes).map_err(ObjLoadError::Utf8)?;
+    ObjModel::parse(source)
+  }
+
+  fn extensions(&self) -> &[&str] { &["obj"] }
+}
+
+/// Draws an [`ObjModel`] once it's loaded.
+#[derive(Component, Clone)]
+#[require(RenderedShape, Transform)]
+pub struct ObjShape {
+  pub model: Handle<ObjModel>,
+  pub style: MeshStyle,
+}
+
+/// Redraws shapes whose model has loaded or changed, and keeps their bounds in
+/// sync with it.
+fn update_obj_shapes(
+  mut commands: Commands,
+  mut events: EventReader<AssetEvent<ObjModel>>,
+  models: Res<Assets<ObjModel>>,
+  mut query: Query<(Entity, Ref<ObjShape>, &mut RenderedShape)>,
+) {
+  let changed_models = events
+    .read()
+    .filter_map(|event| match event {
+      AssetEvent::LoadedWithDependencies { id }
+      | AssetEvent::Modified { id } => Some(*id),
+      _ => None,
+    })
+    .collect::<Vec<_>>();
+
+  for (entity, shape, mut rendered_shape) in query.iter_mut() {
+    if !shape.is_changed() && !changed_models.contains(&shape.model.id()) {
+      continue;
+    }
+
+    rendered_shape.invalidate();
+    match models.get(&shape.model) {
+      Some(model) => commands.entity(entity).insert(model.bounds()),
+      None => commands.entity(entity).remove::<ShapeBounds>(),
+    };
+  }
+}
+
+fn render_obj_shapes(
+  canvas_args: CanvasArgs,
+  models: Res<Assets<ObjModel>>,
+  mut query: Query<(
+    &ObjShape,
+    &Transform,
+    &ShapeVisibility,
+    &mut RenderedShape,
+  )>,
+) {
+  for (shape, transform, visibility, mut buffer) in query.iter_mut() {
+    if !visibility.is_visible() {
+      continue;
+    }
+    // shapes are redrawn once their model loads
+    let Some(model) = models.get(&shape.model) else {
+      continue;
+    };
+    let Some(buffer) = buffer.redraw_buffer() else {
+      continue;
+    };
+
+    draw_mesh(
+      &model.vertices,
+      &model.triangles,
+      &model.feature_edges,
+      &shape.style,
+      buffer,
+      &canvas_args,
+      transform,
+    );
+  }
+}
+
+/// Adds loading and drawing of OBJ models. Needs the [`AssetPlugin`].
+pub struct ObjPlugin;
+
+impl Plugin for ObjPlugin {
+  fn build(&self, app: &mut App) {
+    app
+      .init_asset::<ObjModel>()
+      .init_asset_loader::<ObjLoader>()
+      .add_systems(PostUpdate, update_obj_shapes)
+      .add_systems(Render, render_obj_shapes);
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const UNIT_QUAD: &str = "
+v 0 0 0
+v 1 0 0
+v 1 1 0
+v 0 1 0
+";
+
+  fn parse_error_line(source: &str) -> usize {
+    match ObjModel::parse(source) {
+      Err(ObjLoadError::Parse { line, .. }) => line,
+      other => panic!("expected a parse error, got {other:?}"),
+    }
+  }
+
+  #[test]
+  fn resolves_positive_and_negative_indices() {
+    assert_eq!(resolve_index(1, 4), Some(0));
+    assert_eq!(resolve_index(4, 4), Some(3));
+    assert_eq!(resolve_index(-1, 4), Some(3));
+    assert_eq!(resolve_index(-4, 4), Some(0));
+
+    assert_eq!(resolve_index(0, 4), None);
+    assert_eq!(resolve_index(5, 4), None);
+    assert_eq!(resolve_index(-5, 4), None);
+    assert_eq!(resolve_index(isize::MIN, 4), None);
+  }
+
+  #[test]
+  fn negative_indices_count_back_from_the_latest_vertex() {
+    let absolute = ObjModel::parse(&format!("{UNIT_QUAD}f 1 2 3")).unwrap();
+    let relative = ObjModel::parse(&format!("{UNIT_QUAD}f -4 -3 -2")).unwrap();
+    assert_eq!(absolute.triangles, relative.triangles);
+    assert_eq!(relative.triangles, vec![[0, 1, 2]]);
+  }
+
+  #[test]
+  fn reads_only_positions_from_face_vertices() {
+    let model = ObjModel::parse(&format!(
+      "{UNIT_QUAD}vt 0 0\nvn 0 0 1\nf 1/1/1 2//1 3/1\n"
+    ))
+    .unwrap();
+    assert_eq!(model.triangles, vec![[0, 1, 2]]);
+  }
+
+  #[test]
+  fn triangulates_polygons_as_fans() {
+    let model = ObjModel::parse(&format!("{UNIT_QUAD}f 1 2 3 4")).unwrap();
+    assert_eq!(model.triangles, vec![[0, 1, 2], [0, 2, 3]]);
+  }
+
+  #[test]
+  fn merges_vertices_at_the_same_position() {
+    let model = ObjModel::parse(
+      "
+v 0 0 0
+v 1 0 0
+v 1 1 0
+v 0 0 0
+v 1 1 0
+v 0 1 0
+f 1 2 3
+f 4 5 6
+",
+    )
+    .unwrap();
+    assert_eq!(model.vertices.len(), 4);
+    assert_eq!(model.triangles, vec![[0, 1, 2], [0, 2, 3]]);
+  }
+
+  #[test]
+  fn ignores_comments_and_unknown_lines() {
+    let model = ObjModel::parse(&format!(
+      "# a quad\no quad\ng group\nusemtl stone\n{UNIT_QUAD}f 1 2 3 # first\n"
+    ))
+    .unwrap();
+    assert_eq!(model.triangles, vec![[0, 1, 2]]);
+  }
+
+  #[test]
+  fn rejects_malformed_lines() {
+    assert_eq!(parse_error_line("v 0 0"), 1);
+    assert_eq!(parse_error_line("v 0 zero 0"), 1);
+    assert_eq!(parse_error_line(&format!("{UNIT_QUAD}f 1 2")), 6);
+    assert_eq!(parse_error_line(&format!("{UNIT_QUAD}f 1 two 3")), 6);
+    assert_eq!(parse_error_line(&format!("{UNIT_QUAD}f 1 2 /3")), 6);
+  }
+
+  #[test]
+  fn rejects_out_of_range_indices() {
+    assert_eq!(parse_error_line(&format!("{UNIT_QUAD}f 0 1 2")), 6);
+    assert_eq!(parse_error_line(&format!("{UNIT_QUAD}f 1 2 5")), 6);
+    assert_eq!(parse_error_line(&format!("{UNIT_QUAD}f -5 1 2")), 6);
+    // indices can only refer to vertices defined before the face
+    assert_eq!(parse_error_line("v 0 0 0\nf 1 2 3\nv 1 0 0\nv 1 1 0"), 2);
+  }
+
+  #[test]
+  fn leaves_coplanar_seams_out_of_feature_edges() {
+    let model = ObjModel::parse(&format!("{UNIT_QUAD}f 1 2 3 4")).unwrap();
+    // the diagonal between the quad's two triangles isn't a feature edge
+    assert_eq!(model.feature_edges, vec![(0, 1), (0, 3), (1, 2), (2, 3)]);
+  }
+
+  #[test]
+  fn keeps_sharp_edges_as_feature_edges() {
+    let cube = ObjModel::parse(
+      "
+v 0 0 0
+v 1 0 0
+v 1 1 0
+v 0 1 0
+v 0 0 1
+v 1 0 1
+v 1 1 1
+v 0 1 1
+f 1 4 3 2
+f 5 6 7 8
+f 1 2 6 5
+f 2 3 7 6
+f 3 4 8 7
+f 4 1 5 8
+",
+    )
+    .unwrap();
+    // a cube's 12 edges, without the diagonals across its faces
+    assert_eq!(cube.triangles.len(), 12);
+    assert_eq!(cube.feature_edges.len(), 12);
+    assert!(
+      cube
+        .feature_edges
+        .iter()
+        .all(|&(a, b)| cube.vertices[a].distance(cube.vertices[b]) == 1.0)
+    );
+  }
+}
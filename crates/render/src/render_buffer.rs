@@ -1,8 +1,14 @@
-use bevy::{math::vec2, prelude::*};
+use bevy::{
+  ecs::system::SystemChangeTick,
+  math::{uvec2, vec2},
+  prelude::*,
+};
 use ratatui::{buffer::Buffer, prelude::Rect};
 
-use super::DEFAULT_CELL;
-use crate::{gizmo::GizmoBuffer, shapes::RenderedShape};
+use crate::{
+  camera::CameraMatrix, gizmo::GizmoBuffer, lighting::SceneLights,
+  shapes::RenderedShape, theme::Theme,
+};
 
 #[derive(Default)]
 pub struct RenderedWidgetState {
@@ -17,15 +23,31 @@ impl RenderedWidgetState {
   pub fn buffer_mut(&mut self) -> &mut Buffer { &mut self.buffer }
 }
 
-#[derive(Resource, Default, Clone)]
-pub struct RenderBufferSize(UVec2);
+/// The size of a camera's render buffer.
+///
+/// As a component, this lives on each camera. As a resource, it holds the size
+/// of the camera currently being rendered.
+#[derive(Component, Resource, Default, Clone, PartialEq)]
+pub struct RenderBufferSize(pub(crate) UVec2);
 
 const Y_FLIP: Vec2 = vec2(1.0, -1.0);
 
 impl RenderBufferSize {
+  /// Maps a point in NDC to the canvas cell it falls in.
+  ///
+  /// Canvas coordinates are signed, with the origin at the top left cell of
+  /// the render buffer. Points off the canvas map to cells outside of
+  /// `[0, size)`, flooring so that the cells just past the top left edge don't
+  /// collapse onto it.
   pub fn ndc_to_canvas_coords(&self, point: Vec2) -> IVec2 {
+    self.ndc_to_canvas_position(point).floor().as_ivec2()
+  }
+
+  /// Like [`ndc_to_canvas_coords`](Self::ndc_to_canvas_coords), but without
+  /// snapping to a cell.
+  pub fn ndc_to_canvas_position(&self, point: Vec2) -> Vec2 {
     // map from [-1, 1] to [0, self.0], flipping y (y is down in canvas)
-    ((point * Y_FLIP + 1.0) / 2.0 * self.0.as_vec2()).as_ivec2()
+    (point * Y_FLIP + 1.0) / 2.0 * self.0.as_vec2()
   }
 
   #[allow(dead_code)]
@@ -36,10 +58,16 @@ impl RenderBufferSize {
       * Y_FLIP
   }
 
+  pub fn size(&self) -> UVec2 { self.0 }
+
+  /// The size multiplied by `scale`, e.g. to get the size in canvas points.
+  pub fn scaled(&self, scale: UVec2) -> Self { Self(self.0 * scale) }
+
   pub fn aspect_ratio(&self) -> f32 { self.0.x as f32 / self.0.y as f32 }
 }
 
-#[derive(Resource, Default)]
+/// A camera's render target.
+#[derive(Component, Default)]
 pub struct RenderBuffer {
   widget_state: RenderedWidgetState,
 }
@@ -51,6 +79,8 @@ impl RenderBuffer {
     }
   }
 
+  pub fn widget_state(&self) -> &RenderedWidgetState { &self.widget_state }
+
   pub fn widget_state_mut(&mut self) -> &mut RenderedWidgetState {
     &mut self.widget_state
   }
@@ -59,33 +89,65 @@ impl RenderBuffer {
 
   /// Clears the render buffer and resizes it based off the area in the widget
   /// state.
-  fn update_render_buffer_size(&mut self) {
+  fn update_render_buffer_size(&mut self, theme: &Theme) {
     let area = self.render_area();
 
     self.widget_state.buffer_mut().resize(area);
-    self.widget_state.buffer_mut().content.fill(DEFAULT_CELL);
+    self
+      .widget_state
+      .buffer_mut()
+      .content
+      .fill(theme.default_cell());
   }
 }
 
 pub(crate) fn prepare_for_frame(
-  mut render_buffer_size: ResMut<RenderBufferSize>,
-  mut render_buffer: ResMut<RenderBuffer>,
-  mut sb_query: Query<&mut RenderedShape>,
-  mut gizmo_buffer: ResMut<GizmoBuffer>,
+  mut query: Query<(&mut RenderBuffer, &mut RenderBufferSize)>,
+  theme: Res<Theme>,
 ) {
-  // propagate render area to `RenderBufferSize`
-  let area = render_buffer.render_area();
-  render_buffer_size.0.x = area.width as _;
-  render_buffer_size.0.y = area.height as _;
-
-  // resize the render buffer to what the widget used last
-  render_buffer.update_render_buffer_size();
+  for (mut render_buffer, mut render_buffer_size) in query.iter_mut() {
+    // propagate render area to `RenderBufferSize`
+    let area = render_buffer.render_area();
+    render_buffer_size
+      .set_if_neq(RenderBufferSize(uvec2(area.width as _, area.height as _)));
+
+    // resize the render buffer to what the widget used last
+    render_buffer.update_render_buffer_size(&theme);
+  }
+}
 
-  // update the extent field of all existing shape buffers
-  for mut sb in sb_query.iter_mut() {
-    sb.inner_mut().update_extent(render_buffer_size.0);
+/// Points every shape's retained buffer at the camera currently being
+/// rendered, clearing the ones that need to be redrawn.
+pub(crate) fn prepare_shape_buffers(
+  In(camera): In<Entity>,
+  render_buffer_size: Res<RenderBufferSize>,
+  camera_query: Query<(Ref<CameraMatrix>, Ref<RenderBufferSize>)>,
+  mut sb_query: Query<(&mut RenderedShape, Option<Ref<Transform>>)>,
+  lights: Res<SceneLights>,
+  mut gizmo_buffer: ResMut<GizmoBuffer>,
+  ticks: SystemChangeTick,
+) {
+  let Ok((camera_matrix, camera_size)) = camera_query.get(camera) else {
+    return;
+  };
+  // the lights change how faces are shaded, so every shape is redrawn too
+  let shared_ticks = [
+    camera_matrix.last_changed(),
+    camera_size.last_changed(),
+    lights.last_changed(),
+  ];
+  let canvas_size = render_buffer_size
+    .scaled(camera_matrix.resolution().points_per_cell())
+    .size();
+
+  for (mut sb, transform) in sb_query.iter_mut() {
+    let transform_tick = transform.map(|t| t.last_changed());
+    sb.activate_camera(camera, canvas_size, |drawn_tick| {
+      shared_ticks
+        .iter()
+        .chain(transform_tick.as_ref())
+        .any(|tick| tick.is_newer_than(drawn_tick, ticks.this_run()))
+    });
   }
-  gizmo_buffer
-    .buffer_mut()
-    .update_extent(render_buffer_size.0);
+  gizmo_buffer.update_extent(canvas_size);
 }
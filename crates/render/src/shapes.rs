@@ -1,34 +1,168 @@
+mod arc;
+mod bezier;
+mod box_neighbor;
 mod circle;
+mod clip;
+mod cone;
 mod cuboid;
+mod curve;
+mod cylinder;
 mod line;
 mod material;
+mod mesh;
 mod plane;
 mod polyline;
 mod projected_point;
+mod round;
+mod shading;
 mod shape_buffer;
 mod sign;
-mod thin_neighbor;
+mod sphere;
+pub(crate) mod thin_neighbor;
 
-use bevy::{ecs::system::SystemParam, prelude::*};
+use bevy::{
+  ecs::{component::Tick, entity::EntityHashMap, system::SystemParam},
+  prelude::*,
+};
 
 pub use self::{
-  circle::*, cuboid::*, line::*, material::*, plane::*, polyline::*,
-  projected_point::*, shape_buffer::*, sign::*,
+  arc::*, bezier::*, circle::*, clip::*, cone::*, cuboid::*, curve::*,
+  cylinder::*, line::*, material::*, mesh::*, plane::*, polyline::*,
+  projected_point::*, round::*, shading::*, shape_buffer::*, sign::*,
+  sphere::*,
+};
+use super::camera::{ActiveCameraMatrix, Camera};
+use crate::{
+  culling::{ShapeBounds, ShapeVisibility},
+  lighting::SceneLights,
+  render_buffer::RenderBufferSize,
 };
-use super::camera::MainCameraMatrix;
-use crate::render_buffer::RenderBufferSize;
 
+/// The drawn cells of a shape, retained across frames for each camera.
+///
+/// A camera's buffer is only redrawn when the entity's [`Transform`], the
+/// camera's matrix or its render size, or the scene's lights change, or when
+/// the shape is [invalidated](RenderedShape::invalidate). Buffers are dropped
+/// along with their cameras.
+///
+/// Changes are detected with Bevy's change ticks, so any mutable access counts
+/// as a change even if nothing is different. Systems that keep these in sync
+/// every frame should write them with `set_if_neq`, or every shape is redrawn
+/// every frame.
 #[derive(Component, Reflect, Default)]
-pub struct RenderedShape(#[reflect(ignore)] ShapeBuffer);
+#[require(ShapeVisibility)]
+pub struct RenderedShape {
+  #[reflect(ignore)]
+  buffers:       EntityHashMap<RetainedShapeBuffer>,
+  /// The camera currently being rendered.
+  #[reflect(ignore)]
+  active_camera: Option<Entity>,
+}
+
+#[derive(Default)]
+struct RetainedShapeBuffer {
+  buffer:     ShapeBuffer,
+  /// When the buffer was last drawn, or `None` if it needs to be redrawn.
+  drawn_tick: Option<Tick>,
+}
 
 impl RenderedShape {
-  pub fn inner_mut(&mut self) -> &mut ShapeBuffer { &mut self.0 }
+  /// Returns the buffer to draw into for the camera currently being rendered,
+  /// or `None` if what was drawn into it before is still up to date.
+  pub fn redraw_buffer(&mut self) -> Option<&mut ShapeBuffer> {
+    let retained = self.buffers.get_mut(&self.active_camera?)?;
+    retained
+      .drawn_tick
+      .is_none()
+      .then_some(&mut retained.buffer)
+  }
+
+  /// Clears the shape for every camera, so that it gets redrawn. Use this when
+  /// something other than the entity's [`Transform`] changes how it looks.
+  pub fn invalidate(&mut self) {
+    for retained in self.buffers.values_mut() {
+      retained.buffer.clear();
+      retained.drawn_tick = None;
+    }
+  }
+
+  /// Makes `camera` the one being rendered, clearing its buffer if
+  /// `changed_since` reports that its inputs changed since the tick it was
+  /// last drawn at.
+  pub(crate) fn activate_camera(
+    &mut self,
+    camera: Entity,
+    extent: UVec2,
+    changed_since: impl Fn(Tick) -> bool,
+  ) {
+    self.active_camera = Some(camera);
+
+    let retained = self.buffers.entry(camera).or_default();
+    retained.buffer.update_extent(extent);
+
+    if retained.drawn_tick.is_none_or(changed_since) {
+      retained.buffer.clear();
+      retained.drawn_tick = None;
+    }
+  }
+
+  /// Drops the buffer kept for `camera`, once it's gone.
+  fn forget_camera(&mut self, camera: Entity) {
+    self.buffers.remove(&camera);
+    if self.active_camera == Some(camera) {
+      self.active_camera = None;
+    }
+  }
+
+  /// The buffer of the camera currently being rendered.
+  pub fn active_buffer(&self) -> Option<&ShapeBuffer> {
+    self
+      .buffers
+      .get(&self.active_camera?)
+      .map(|retained| &retained.buffer)
+  }
+
+  /// Marks the active camera's buffer as drawn at `tick`, if it was redrawn.
+  ///
+  /// Returns whether it was redrawn.
+  pub(crate) fn mark_drawn(&mut self, tick: Tick) -> bool {
+    let Some(retained) = self
+      .active_camera
+      .and_then(|camera| self.buffers.get_mut(&camera))
+    else {
+      return false;
+    };
+    if retained.drawn_tick.is_some() {
+      return false;
+    }
+    retained.drawn_tick = Some(tick);
+    true
+  }
+}
+
+/// Drops the buffers every shape kept for cameras that were despawned, or
+/// stopped being cameras.
+pub(crate) fn forget_removed_cameras(
+  mut removed_cameras: RemovedComponents<Camera>,
+  mut query: Query<&mut RenderedShape>,
+) {
+  let removed_cameras = removed_cameras.read().collect::<Vec<_>>();
+  if removed_cameras.is_empty() {
+    return;
+  }
+
+  for mut shape in query.iter_mut() {
+    for camera in &removed_cameras {
+      shape.forget_camera(*camera);
+    }
+  }
 }
 
 #[derive(SystemParam)]
 pub struct CanvasArgs<'w> {
-  camera_matrix:      Res<'w, MainCameraMatrix>,
+  camera_matrix:      Res<'w, ActiveCameraMatrix>,
   render_buffer_size: Res<'w, RenderBufferSize>,
+  lights:             Res<'w, SceneLights>,
 }
 
 impl Clone for CanvasArgs<'_> {
@@ -36,30 +170,72 @@ impl Clone for CanvasArgs<'_> {
     CanvasArgs {
       camera_matrix:      Res::clone(&self.camera_matrix),
       render_buffer_size: Res::clone(&self.render_buffer_size),
+      lights:             Res::clone(&self.lights),
     }
   }
 }
 
 impl CanvasArgs<'_> {
+  pub fn world_to_ndc(&self, point: Vec3) -> Vec3 {
+    self.camera_matrix.world_to_ndc(point)
+  }
+
+  pub fn world_to_view(&self, point: Vec3) -> Vec3 {
+    self.camera_matrix.world_to_view(point)
+  }
+
+  /// Where the camera is in the local space of `transform`, as a homogeneous
+  /// point. `w` is 0 for orthographic cameras, which look from infinitely far
+  /// away back along `xyz`.
+  pub fn local_camera_center(&self, transform: &Transform) -> Vec4 {
+    transform.compute_matrix().inverse() * self.camera_matrix.camera_center()
+  }
+
+  /// How brightly the scene's lights light a surface at `point` facing along
+  /// `normal`, both in world space, from 0 for unlit to 1 for fully lit.
+  pub fn light_intensity(&self, point: Vec3, normal: Vec3) -> f32 {
+    self.lights.intensity(point, normal)
+  }
+
   pub fn world_to_canvas_coords(&self, point: Vec3) -> ProjectedPoint {
     let ndc = self.camera_matrix.world_to_ndc(point);
     ProjectedPoint::new(
-      self.render_buffer_size.ndc_to_canvas_coords(ndc.xy()),
+      self.canvas_size().ndc_to_canvas_coords(ndc.xy()),
       ndc.z,
     )
   }
 
   #[allow(dead_code)]
   pub fn canvas_to_ndc_coords(&self, point: ProjectedPoint) -> Vec3 {
-    let ndc = self.render_buffer_size.canvas_to_ndc_coords(point.pos());
+    let ndc = self.canvas_size().canvas_to_ndc_coords(point.pos());
     Vec3::new(ndc.x, ndc.y, point.depth())
   }
 
+  /// The aspect ratio of a single canvas point.
   pub fn character_aspect_ratio(&self) -> f32 {
-    self.camera_matrix.character_aspect_ratio()
+    let points_per_cell = self.points_per_cell().as_vec2();
+    self.camera_matrix.character_aspect_ratio() * points_per_cell.y
+      / points_per_cell.x
+  }
+
+  /// The number of canvas points across and down each terminal cell.
+  pub fn points_per_cell(&self) -> UVec2 {
+    self.camera_matrix.resolution().points_per_cell()
+  }
+
+  /// The size of the canvas, in canvas points.
+  pub fn canvas_size(&self) -> RenderBufferSize {
+    self.render_buffer_size.scaled(self.points_per_cell())
   }
 }
 
+/// Something that can be drawn into a [`ShapeBuffer`].
+///
+/// Shapes are drawn into the retained buffer of a [`RenderedShape`], which is
+/// only redrawn when the inputs the renderer knows about change (see its
+/// docs). When anything else that changes how a shape looks changes, like
+/// the component the shape is built from, call [`RenderedShape::invalidate`]
+/// or the shape will keep showing what it drew before.
 pub trait DrawnShape {
   fn draw(
     &self,
@@ -67,4 +243,7 @@ pub trait DrawnShape {
     args: &CanvasArgs,
     transform: &Transform,
   );
+
+  /// The local-space bounds of the shape, if it has any.
+  fn bounds(&self) -> Option<ShapeBounds> { None }
 }
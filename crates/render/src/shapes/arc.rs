@@ -0,0 +1,51 @@
+use std::f32::consts::TAU;
+
+use bevy::prelude::*;
+
+use super::{CanvasArgs, CurveStyle, DrawnShape, LocalCircle, ShapeBuffer};
+use crate::culling::ShapeBounds;
+
+/// An arc of a circle around the origin.
+pub struct ArcArgs {
+  pub radius:      f32,
+  /// The angle the arc starts at, in radians counter-clockwise from the X
+  /// axis of its plane.
+  pub start_angle: f32,
+  /// The angle the arc ends at. The arc runs clockwise if it's less than
+  /// `start_angle`.
+  pub end_angle:   f32,
+  /// The rotation from the local XY plane to the plane the arc lies in.
+  pub plane:       Quat,
+  pub style:       CurveStyle,
+}
+
+impl DrawnShape for ArcArgs {
+  fn draw(
+    &self,
+    buffer: &mut ShapeBuffer,
+    args: &CanvasArgs,
+    transform: &Transform,
+  ) {
+    let circle = LocalCircle::in_plane(self.plane, self.radius);
+    let sweep = self.end_angle - self.start_angle;
+
+    // split the arc as finely as its whole circle would be
+    let circle_segments = circle.segment_count(args, transform);
+    let n_segments =
+      ((circle_segments as f32 * sweep.abs() / TAU).ceil() as usize).max(1);
+
+    let points = (0..=n_segments)
+      .map(|i| {
+        let angle = self.start_angle + sweep * i as f32 / n_segments as f32;
+        circle.point_at(angle)
+      })
+      .collect();
+    self.style.draw_points(points, buffer, args, transform);
+  }
+
+  fn bounds(&self) -> Option<ShapeBounds> {
+    // the bounds of the whole circle, which always contain the arc
+    let circle = LocalCircle::in_plane(self.plane, self.radius);
+    Some(ShapeBounds::new(Vec3::ZERO, circle.half_extents()))
+  }
+}
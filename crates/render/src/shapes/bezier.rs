@@ -0,0 +1,114 @@
+use bevy::prelude::*;
+
+use super::{
+  CANVAS_POINTS_PER_SEGMENT, CanvasArgs, CurveStyle, DrawnShape, ShapeBuffer,
+};
+use crate::culling::ShapeBounds;
+
+/// How many times a Bézier curve can be split in half, which caps it at
+/// `2^MAX_SUBDIVISION_DEPTH` segments.
+const MAX_SUBDIVISION_DEPTH: u32 = 8;
+/// How much longer than its chord a piece of the curve's control polygon
+/// can be on the canvas, in canvas points, before it's too curved to draw as
+/// a single segment.
+const FLATNESS_TOLERANCE: f32 = 0.25;
+
+/// A quadratic or cubic Bézier curve. Its control points are in the shape's
+/// local space, so they're in world space under an identity transform.
+pub struct BezierArgs {
+  pub curve: BezierCurve,
+  pub style: CurveStyle,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub enum BezierCurve {
+  /// A curve from the first point to the last, pulled towards the middle one.
+  Quadratic([Vec3; 3]),
+  /// A curve from the first point to the last, leaving towards the second
+  /// and arriving from the third.
+  Cubic([Vec3; 4]),
+}
+
+impl BezierCurve {
+  fn control_points(&self) -> &[Vec3] {
+    match self {
+      BezierCurve::Quadratic(points) => points,
+      BezierCurve::Cubic(points) => points,
+    }
+  }
+}
+
+impl DrawnShape for BezierArgs {
+  fn draw(
+    &self,
+    buffer: &mut ShapeBuffer,
+    args: &CanvasArgs,
+    transform: &Transform,
+  ) {
+    let control_points = self.curve.control_points();
+    let mut points = vec![control_points[0]];
+    subdivide(control_points, args, transform, 0, &mut points);
+    self.style.draw_points(points, buffer, args, transform);
+  }
+
+  fn bounds(&self) -> Option<ShapeBounds> {
+    // a Bézier curve never leaves the hull of its control points
+    Some(ShapeBounds::from_points(
+      self.curve.control_points().iter().copied(),
+    ))
+  }
+}
+
+/// Pushes the points that end each segment of the curve with these control
+/// points, splitting it in half until each piece is short or flat on the
+/// canvas.
+fn subdivide(
+  control_points: &[Vec3],
+  args: &CanvasArgs,
+  transform: &Transform,
+  depth: u32,
+  points: &mut Vec<Vec3>,
+) {
+  let last = *control_points.last().unwrap();
+
+  let canvas = control_points
+    .iter()
+    .map(|p| args.world_to_canvas_position(transform.transform_point(*p)))
+    .collect::<Vec<_>>();
+  let polygon_length = canvas
+    .windows(2)
+    .map(|pair| pair[0].distance(pair[1]))
+    .sum::<f32>();
+  let chord_length = canvas[0].distance(*canvas.last().unwrap());
+
+  let is_done = polygon_length <= CANVAS_POINTS_PER_SEGMENT
+    || polygon_length - chord_length <= FLATNESS_TOLERANCE;
+  if depth >= MAX_SUBDIVISION_DEPTH || is_done {
+    points.push(last);
+    return;
+  }
+
+  let (first_half, second_half) = split_in_half(control_points);
+  subdivide(&first_half, args, transform, depth + 1, points);
+  subdivide(&second_half, args, transform, depth + 1, points);
+}
+
+/// Splits a Bézier curve at its middle with de Casteljau's algorithm,
+/// returning the control points of each half.
+fn split_in_half(control_points: &[Vec3]) -> (Vec<Vec3>, Vec<Vec3>) {
+  let mut first_half = Vec::with_capacity(control_points.len());
+  let mut second_half = Vec::with_capacity(control_points.len());
+
+  let mut level = control_points.to_vec();
+  while let Some((&first, _)) = level.split_first() {
+    first_half.push(first);
+    second_half.push(*level.last().unwrap());
+    level = level
+      .windows(2)
+      .map(|pair| pair[0].midpoint(pair[1]))
+      .collect();
+  }
+
+  second_half.reverse();
+  (first_half, second_half)
+}
@@ -0,0 +1,29 @@
+use ratatui::symbols::line;
+
+use super::thin_neighbor::{Neighbor, Neighbor::*, thin_neighbor_symbol};
+
+/// Picks a box-drawing symbol from `set` for a cell whose line comes in from
+/// `from` and leaves towards `to`.
+///
+/// Box-drawing characters only come in straight runs and right-angled
+/// corners, so shallow and steep steps are drawn as straight runs, and lines
+/// that turn between diagonals fall back to the thin symbols.
+pub fn box_neighbor_symbol(
+  from: Neighbor,
+  to: Neighbor,
+  set: &line::Set,
+) -> &'static str {
+  match (from, to) {
+    (Left | Right, Left | Right) => set.horizontal,
+    (Top | Bottom, Top | Bottom) => set.vertical,
+    (Right, Bottom) | (Bottom, Right) => set.top_left,
+    (Left, Bottom) | (Bottom, Left) => set.top_right,
+    (Right, Top) | (Top, Right) => set.bottom_left,
+    (Left, Top) | (Top, Left) => set.bottom_right,
+    (TopRight | BottomLeft, TopRight | BottomLeft) => "╱",
+    (TopLeft | BottomRight, TopLeft | BottomRight) => "╲",
+    (Left | Right, _) | (_, Left | Right) => set.horizontal,
+    (Top | Bottom, _) | (_, Top | Bottom) => set.vertical,
+    _ => thin_neighbor_symbol(from, to),
+  }
+}
@@ -1,20 +1,28 @@
-use std::f32::consts::PI;
-
 use bevy::prelude::*;
 
 use super::{
-  CanvasArgs, DrawnShape, Material, PolylineArgs, PolylineLoopStyle,
-  PolylineStyle, ShapeBuffer,
+  CanvasArgs, DrawnShape, HiddenLineStyle, LinePattern, LineVariant,
+  LocalCircle, Material, PlaneStyle, PolylineArgs, PolylineLoopStyle,
+  PolylineStyle, ShapeBuffer, mesh::fill_triangles,
 };
+use crate::culling::ShapeBounds;
 
 pub struct CircleArgs {
   pub radius: f32,
+  /// The rotation from the local XY plane to the plane the circle lies in.
+  pub plane:  Quat,
   pub style:  CircleStyle,
 }
 
 #[derive(Clone)]
 pub struct CircleStyle {
   pub material: Material,
+  pub variant:  LineVariant,
+  pub pattern:  LinePattern,
+  /// How the hidden parts are drawn. See [`HiddenLineStyle`].
+  pub hidden:   Option<HiddenLineStyle>,
+  /// The style the inside of the circle is filled with, if it's filled.
+  pub fill:     Option<PlaneStyle>,
 }
 
 impl DrawnShape for CircleArgs {
@@ -24,32 +32,26 @@ impl DrawnShape for CircleArgs {
     args: &CanvasArgs,
     transform: &Transform,
   ) {
-    let CircleArgs { radius, style } = self;
-
-    let transformed_center = transform.transform_point(Vec3::ZERO);
-    let canvas_center = args.world_to_canvas_coords(transformed_center);
-
-    let canvas_positive_x = args.world_to_canvas_coords(
-      transformed_center + Vec3::new(*radius, 0.0, 0.0),
-    );
-    let canvas_positive_y = args.world_to_canvas_coords(
-      transformed_center + Vec3::new(0.0, *radius, 0.0),
-    );
-
-    // treat the transformed version like an ellipse and calculate perimeter
-    let x_radius = (canvas_positive_x.pos() - canvas_center.pos())
-      .as_vec2()
-      .length();
-    let y_radius = (canvas_positive_y.pos() - canvas_center.pos())
-      .as_vec2()
-      .length();
-    let estimated_canvas_perimeter = (x_radius + y_radius) * PI;
-
-    let n_segments = (estimated_canvas_perimeter / 5.0).ceil() as usize;
-    // let n_segments = 20;
-
-    let iter = CircleSegmentIterator::new(*radius, n_segments);
-    let points: Vec<_> = iter.collect();
+    let CircleArgs {
+      radius,
+      plane,
+      style,
+    } = self;
+
+    let circle = LocalCircle::in_plane(*plane, *radius);
+    let n_segments = circle.segment_count(args, transform);
+    let points = circle.points(n_segments).collect::<Vec<_>>();
+
+    if let Some(fill) = &style.fill {
+      // fill the disc as a fan around its center
+      let vertices = std::iter::once(Vec3::ZERO)
+        .chain(points.iter().copied())
+        .collect::<Vec<_>>();
+      let triangles = (0..n_segments)
+        .map(|i| [0, i + 1, (i + 1) % n_segments + 1])
+        .collect::<Vec<_>>();
+      fill_triangles(&vertices, &triangles, fill, buffer, args, transform);
+    }
 
     let polyline_args = PolylineArgs {
       points,
@@ -58,43 +60,16 @@ impl DrawnShape for CircleArgs {
         loop_style: PolylineLoopStyle::Closed {
           point_cap_material: None,
         },
+        variant:    style.variant,
+        pattern:    style.pattern,
+        hidden:     style.hidden.clone(),
       },
     };
     polyline_args.draw(buffer, args, transform);
   }
-}
-
-/// An iterator that takes world-space circle paramteters and returns
-/// world-space
-struct CircleSegmentIterator {
-  radius:     f32,
-  n_segments: usize,
-  angle:      f32,
-}
-
-impl CircleSegmentIterator {
-  fn new(radius: f32, n_segments: usize) -> CircleSegmentIterator {
-    CircleSegmentIterator {
-      radius,
-      n_segments,
-      angle: 0.0,
-    }
-  }
-}
-
-impl Iterator for CircleSegmentIterator {
-  type Item = Vec3;
-
-  fn next(&mut self) -> Option<Self::Item> {
-    if self.angle >= 2.0 * PI {
-      return None;
-    }
-
-    let x = self.angle.cos();
-    let y = self.angle.sin();
-    let point = Vec3::new(x * self.radius, y * self.radius, 0.0);
-    self.angle += 2.0 * PI / self.n_segments as f32;
 
-    Some(point)
+  fn bounds(&self) -> Option<ShapeBounds> {
+    let circle = LocalCircle::in_plane(self.plane, self.radius);
+    Some(ShapeBounds::new(Vec3::ZERO, circle.half_extents()))
   }
 }
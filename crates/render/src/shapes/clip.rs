@@ -0,0 +1,347 @@
+use bevy::prelude::*;
+
+use super::{CanvasArgs, ProjectedPoint};
+
+/// How far past the edge of the canvas segments are clipped, in cells.
+///
+/// Keeping a cell beyond the edge means the cells on the edge still have a
+/// neighbor to pick their glyph from. The extra cell is thrown away by the
+/// [`ShapeBuffer`](super::ShapeBuffer).
+const CLIP_MARGIN: f32 = 1.0;
+
+/// A segment that has been clipped to the canvas and the visible depth range.
+#[derive(Clone, Copy, Debug)]
+pub struct ClippedSegment {
+  pub from:          ProjectedPoint,
+  pub to:            ProjectedPoint,
+  /// Whether `from` was moved by clipping, so isn't a real endpoint.
+  pub from_clipped:  bool,
+  /// Whether `to` was moved by clipping, so isn't a real endpoint.
+  pub to_clipped:    bool,
+  /// How many cells were clipped off the start of the segment by the edge of
+  /// the canvas.
+  pub skipped_cells: u32,
+}
+
+impl CanvasArgs<'_> {
+  /// Projects a world-space segment onto the canvas, clipping it to the
+  /// canvas and the visible depth range. Returns `None` if none of it is
+  /// visible.
+  pub fn clip_segment(&self, from: Vec3, to: Vec3) -> Option<ClippedSegment> {
+    self.clip_view_segment(self.world_to_view(from), self.world_to_view(to))
+  }
+
+  /// Projects a world-space point onto the canvas without snapping it to a
+  /// cell or checking its depth.
+  pub fn world_to_canvas_position(&self, point: Vec3) -> Vec2 {
+    self
+      .canvas_size()
+      .ndc_to_canvas_position(self.world_to_ndc(point).xy())
+  }
+
+  /// Projects a view-space point onto the canvas without snapping it to a
+  /// cell or checking its depth.
+  pub fn view_to_canvas_position(&self, point: Vec3) -> Vec2 {
+    self
+      .canvas_size()
+      .ndc_to_canvas_position(self.camera_matrix.view_to_ndc(point).xy())
+  }
+
+  /// The linear depth of a view-space point, which is 0 at the near plane and
+  /// 1 at the far plane.
+  pub fn view_depth(&self, point: Vec3) -> f32 {
+    self.camera_matrix.view_depth(point)
+  }
+
+  /// The clip-space `w` of a view-space point, which it's divided by when
+  /// it's projected.
+  pub fn view_clip_w(&self, point: Vec3) -> f32 {
+    self.camera_matrix.view_to_clip(point).w
+  }
+
+  /// Whether the front of a world-space triangle faces the camera, where its
+  /// front is the side its corners wind counter-clockwise around.
+  pub fn is_front_facing(&self, corners: [Vec3; 3]) -> bool {
+    let [a, b, c] = corners
+      .map(|p| self.camera_matrix.view_to_clip(self.world_to_view(p)).xyw());
+    // this is the winding on the canvas scaled by each corner's `w`, so it
+    // stays correct for corners behind the camera
+    Mat3::from_cols(a, b, c).determinant() > 0.0
+  }
+
+  /// Like [`clip_segment`](Self::clip_segment), but for a view-space segment.
+  pub fn clip_view_segment(
+    &self,
+    from: Vec3,
+    to: Vec3,
+  ) -> Option<ClippedSegment> {
+    let camera = &self.camera_matrix;
+    let size = self.canvas_size();
+
+    // clip to the depth range first, so that nothing behind the camera gets
+    // projected. depth is linear along the segment in view space.
+    let (t0, t1) = self.view_depth_range(from, to)?;
+    let from_view = if t0 > 0.0 { from.lerp(to, t0) } else { from };
+    let to_view = if t1 < 1.0 { from.lerp(to, t1) } else { to };
+    let from_depth = camera.view_depth(from_view);
+    let to_depth = camera.view_depth(to_view);
+
+    // then clip to the canvas, in canvas space
+    let from_canvas =
+      size.ndc_to_canvas_position(camera.view_to_ndc(from_view).xy());
+    let to_canvas =
+      size.ndc_to_canvas_position(camera.view_to_ndc(to_view).xy());
+    let delta = to_canvas - from_canvas;
+    let min = Vec2::splat(-CLIP_MARGIN);
+    let max = size.size().as_vec2() + CLIP_MARGIN;
+
+    let range =
+      clip_parameter_range((0.0, 1.0), from_canvas.x, delta.x, min.x, max.x)?;
+    let (s0, s1) =
+      clip_parameter_range(range, from_canvas.y, delta.y, min.y, max.y)?;
+
+    // canvas space is linear in `1 / w` along the segment, so depth has to be
+    // interpolated through it to be correct under perspective
+    let from_w = camera.view_to_clip(from_view).w;
+    let to_w = camera.view_to_clip(to_view).w;
+    let point_at = |s: f32| {
+      // use the endpoints as-is when they weren't clipped, to avoid rounding
+      let (position, t) = if s <= 0.0 {
+        (from_canvas, 0.0)
+      } else if s >= 1.0 {
+        (to_canvas, 1.0)
+      } else {
+        let t = (s / to_w) / ((1.0 - s) / from_w + s / to_w);
+        (from_canvas + delta * s, t)
+      };
+      let depth = from_depth + (to_depth - from_depth) * t;
+      ProjectedPoint::new(position.floor().as_ivec2(), depth.clamp(0.0, 1.0))
+    };
+
+    Some(ClippedSegment {
+      from:          point_at(s0),
+      to:            point_at(s1),
+      from_clipped:  t0 > 0.0 || s0 > 0.0,
+      to_clipped:    t1 < 1.0 || s1 < 1.0,
+      skipped_cells: (delta * s0).abs().max_element().round() as u32,
+    })
+  }
+
+  /// The range of parameters along a view-space segment that lie within the
+  /// visible depth range, or `None` if none of it does.
+  pub fn view_depth_range(&self, from: Vec3, to: Vec3) -> Option<(f32, f32)> {
+    let from_depth = self.camera_matrix.view_depth(from);
+    let to_depth = self.camera_matrix.view_depth(to);
+    clip_parameter_range(
+      (0.0, 1.0),
+      from_depth,
+      to_depth - from_depth,
+      0.0,
+      1.0,
+    )
+  }
+
+  /// How many cells a point moving along a view-space segment travels on the
+  /// canvas per unit of its parameter, at parameter `t`.
+  pub fn canvas_speed(&self, from: Vec3, to: Vec3, t: f32) -> f32 {
+    let camera = &self.camera_matrix;
+    let clip = camera.view_to_clip(from.lerp(to, t));
+    let clip_delta = camera.view_to_clip(to) - camera.view_to_clip(from);
+
+    // the derivative of the perspective divide
+    let ndc_delta =
+      (clip_delta.xy() * clip.w - clip.xy() * clip_delta.w) / (clip.w * clip.w);
+    let canvas_delta = ndc_delta * self.canvas_size().size().as_vec2() / 2.0;
+    canvas_delta.abs().max_element()
+  }
+}
+
+/// Narrows the parameter range `(t0, t1)` to where `start + delta * t` lies
+/// within `min..=max`, or returns `None` if it never does.
+fn clip_parameter_range(
+  (t0, t1): (f32, f32),
+  start: f32,
+  delta: f32,
+  min: f32,
+  max: f32,
+) -> Option<(f32, f32)> {
+  if delta == 0.0 {
+    return (min..=max).contains(&start).then_some((t0, t1));
+  }
+
+  let a = (min - start) / delta;
+  let b = (max - start) / delta;
+  let (enter, exit) = if a < b { (a, b) } else { (b, a) };
+
+  let (t0, t1) = (t0.max(enter), t1.min(exit));
+  (t0 <= t1).then_some((t0, t1))
+}
+
+#[cfg(test)]
+mod tests {
+  use bevy::ecs::system::SystemState;
+
+  use super::*;
+  use crate::{
+    camera::{ActiveCameraMatrix, Camera, CameraProjection},
+    lighting::SceneLights,
+    render_buffer::RenderBufferSize,
+  };
+
+  const SIZE: UVec2 = UVec2::new(40, 20);
+
+  /// Runs `f` with canvas args for a perspective camera at the origin,
+  /// looking down -Z, so world space is view space.
+  fn with_canvas_args(f: impl FnOnce(&CanvasArgs)) {
+    let size = RenderBufferSize(SIZE);
+    let camera =
+      Camera::default().with_projection(CameraProjection::Perspective {
+        fov:  1.0,
+        near: 0.1,
+      });
+    let matrix = camera.calculate_matrix(&Transform::IDENTITY, &size);
+
+    let mut world = World::new();
+    world.insert_resource(ActiveCameraMatrix(matrix));
+    world.insert_resource(size);
+    world.init_resource::<SceneLights>();
+    let mut state = SystemState::<CanvasArgs>::new(&mut world);
+    f(&state.get(&world));
+  }
+
+  #[test]
+  fn parameter_range_is_kept_inside_bounds() {
+    assert_eq!(
+      clip_parameter_range((0.0, 1.0), 2.0, 4.0, 0.0, 10.0),
+      Some((0.0, 1.0))
+    );
+  }
+
+  #[test]
+  fn parameter_range_is_narrowed_where_it_crosses_bounds() {
+    assert_eq!(
+      clip_parameter_range((0.0, 1.0), -5.0, 20.0, 0.0, 10.0),
+      Some((0.25, 0.75))
+    );
+    // the same, travelling the other way
+    assert_eq!(
+      clip_parameter_range((0.0, 1.0), 15.0, -20.0, 0.0, 10.0),
+      Some((0.25, 0.75))
+    );
+    // an already narrowed range stays within itself
+    assert_eq!(
+      clip_parameter_range((0.5, 1.0), -5.0, 20.0, 0.0, 10.0),
+      Some((0.5, 0.75))
+    );
+  }
+
+  #[test]
+  fn parameter_range_is_none_outside_bounds() {
+    assert_eq!(clip_parameter_range((0.0, 1.0), 11.0, 4.0, 0.0, 10.0), None);
+    assert_eq!(clip_parameter_range((0.0, 1.0), -5.0, 4.0, 0.0, 10.0), None);
+    // segments parallel to the bounds are all in or all out
+    assert_eq!(
+      clip_parameter_range((0.0, 1.0), 5.0, 0.0, 0.0, 10.0),
+      Some((0.0, 1.0))
+    );
+    assert_eq!(clip_parameter_range((0.0, 1.0), 11.0, 0.0, 0.0, 10.0), None);
+  }
+
+  #[test]
+  fn segments_inside_the_canvas_are_kept_whole() {
+    with_canvas_args(|args| {
+      let segment = args
+        .clip_view_segment(
+          Vec3::new(-0.2, 0.0, -2.0),
+          Vec3::new(0.2, 0.1, -3.0),
+        )
+        .unwrap();
+      assert!(!segment.from_clipped);
+      assert!(!segment.to_clipped);
+      assert_eq!(segment.skipped_cells, 0);
+      assert_eq!(
+        segment.from.pos(),
+        args
+          .world_to_canvas_position(Vec3::new(-0.2, 0.0, -2.0))
+          .floor()
+          .as_ivec2()
+      );
+    });
+  }
+
+  #[test]
+  fn segments_outside_the_canvas_are_dropped() {
+    with_canvas_args(|args| {
+      // off to the side
+      let side = args.clip_view_segment(
+        Vec3::new(50.0, 0.0, -2.0),
+        Vec3::new(60.0, 0.0, -3.0),
+      );
+      assert!(side.is_none());
+      // behind the camera
+      let behind = args
+        .clip_view_segment(Vec3::new(0.0, 0.0, 1.0), Vec3::new(1.0, 0.0, 2.0));
+      assert!(behind.is_none());
+    });
+  }
+
+  #[test]
+  fn segments_crossing_the_canvas_edge_are_cut_at_the_margin() {
+    with_canvas_args(|args| {
+      let segment = args
+        .clip_view_segment(
+          Vec3::new(-50.0, 0.0, -2.0),
+          Vec3::new(0.0, 0.0, -2.0),
+        )
+        .unwrap();
+      assert!(segment.from_clipped);
+      assert!(!segment.to_clipped);
+      assert_eq!(segment.from.pos().x, -CLIP_MARGIN as i32);
+      assert!(segment.skipped_cells > 0);
+    });
+  }
+
+  #[test]
+  fn segments_crossing_the_near_plane_start_at_it() {
+    with_canvas_args(|args| {
+      let segment = args
+        .clip_view_segment(Vec3::new(0.0, 0.0, 1.0), Vec3::new(0.1, 0.1, -2.0))
+        .unwrap();
+      assert!(segment.from_clipped);
+      assert!(!segment.to_clipped);
+      assert!(segment.from.depth().abs() < 1e-6);
+    });
+  }
+
+  #[test]
+  fn clipped_endpoints_keep_the_depth_of_the_unclipped_line() {
+    with_canvas_args(|args| {
+      let (from, to) =
+        (Vec3::new(-50.0, 0.0, -10.0), Vec3::new(0.5, 0.0, -2.0));
+      let segment = args.clip_view_segment(from, to).unwrap();
+      assert!(segment.from_clipped);
+
+      // find where the line itself crosses the clipping margin, by bisecting
+      // along it in view space
+      let (mut low, mut high) = (0.0, 1.0);
+      for _ in 0..64 {
+        let mid = (low + high) / 2.0;
+        let x = args.view_to_canvas_position(from.lerp(to, mid)).x;
+        if x < -CLIP_MARGIN {
+          low = mid;
+        } else {
+          high = mid;
+        }
+      }
+      let expected = args.view_depth(from.lerp(to, high));
+
+      assert!(
+        (segment.from.depth() - expected).abs() < 1e-6,
+        "clipped depth {} should be {expected}",
+        segment.from.depth()
+      );
+      // the far end isn't clipped, so it keeps its own depth
+      assert!((segment.to.depth() - args.view_depth(to)).abs() < 1e-6);
+    });
+  }
+}
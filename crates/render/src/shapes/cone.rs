@@ -0,0 +1,107 @@
+use bevy::prelude::*;
+
+use super::{
+  CanvasArgs, DrawnShape, LocalCircle, RoundStyle, ShapeBuffer,
+  draw_circle_arcs, draw_polyline, silhouette_angles,
+};
+use crate::culling::ShapeBounds;
+
+/// A cone around the Y axis, centered on the origin with its apex pointing
+/// up. It's drawn as its silhouette and the visible part of its base's rim,
+/// with optional rings around the side facing the camera.
+pub struct ConeArgs {
+  /// The radius of the base.
+  pub radius:      f32,
+  /// Half of the cone's height.
+  pub half_height: f32,
+  /// The number of rings, spaced evenly between the base and the apex.
+  pub rings:       u32,
+  pub style:       RoundStyle,
+}
+
+impl DrawnShape for ConeArgs {
+  fn draw(
+    &self,
+    buffer: &mut ShapeBuffer,
+    args: &CanvasArgs,
+    transform: &Transform,
+  ) {
+    let ConeArgs {
+      radius,
+      half_height,
+      rings,
+      style,
+    } = self;
+    let (radius, half_height) = (*radius, *half_height);
+    let height = 2.0 * half_height;
+    let eye = args.local_camera_center(transform);
+    let apex = Vec3::Y * half_height;
+
+    // the side's outward normal around `direction` is
+    // `(height * direction.x, radius, height * direction.y)`, and its tangent
+    // plane there passes through the apex
+    let side_facing = |point: Vec3| {
+      let direction = point.xz().normalize_or_zero();
+      height * direction.dot(eye.xz()) + radius * eye.y
+        - radius * half_height * eye.w
+    };
+
+    // the side's silhouette is the two lines from the apex whose tangent
+    // plane passes through the camera
+    let k = radius * (half_height * eye.w - eye.y) / height;
+    if let Some(angles) = silhouette_angles(eye, k) {
+      for angle in angles {
+        let (sin, cos) = angle.sin_cos();
+        let base = Vec3::new(cos * radius, -half_height, sin * radius);
+        draw_polyline(
+          vec![base, apex],
+          false,
+          &style.material,
+          style.pattern,
+          buffer,
+          args,
+          transform,
+        );
+      }
+    }
+
+    // the rim is all visible when the base faces the camera, and only where
+    // the side does otherwise
+    let base_facing = -eye.y - half_height * eye.w > 0.0;
+    draw_circle_arcs(
+      LocalCircle::around_y(-half_height, radius),
+      |point| if base_facing { 1.0 } else { side_facing(point) },
+      &style.material,
+      style.pattern,
+      buffer,
+      args,
+      transform,
+    );
+
+    let Some(guide_material) = &style.guide_material else {
+      return;
+    };
+    for i in 1..=*rings {
+      let fraction = i as f32 / (*rings + 1) as f32;
+      draw_circle_arcs(
+        LocalCircle::around_y(
+          half_height * (2.0 * fraction - 1.0),
+          radius * (1.0 - fraction),
+        ),
+        side_facing,
+        guide_material,
+        style.guide_pattern,
+        buffer,
+        args,
+        transform,
+      );
+    }
+  }
+
+  fn bounds(&self) -> Option<ShapeBounds> {
+    Some(ShapeBounds::new(
+      Vec3::ZERO,
+      Vec3::new(self.radius, self.half_height, self.radius),
+    ))
+  }
+}
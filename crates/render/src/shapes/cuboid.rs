@@ -1,11 +1,11 @@
-use std::f32::consts::PI;
-
 use bevy::prelude::*;
+use ratatui::style::Color;
 
 use super::{
-  CanvasArgs, DrawnShape, LineStyle, LineVariant, Material, PlaneArgs,
-  PlaneStyle, ShapeBuffer, line::LineArgs,
+  CanvasArgs, DrawnShape, FaceShading, HiddenLineStyle, LinePattern, LineStyle,
+  LineVariant, Material, PlaneArgs, PlaneStyle, ShapeBuffer, line::LineArgs,
 };
+use crate::culling::ShapeBounds;
 
 pub struct CuboidArgs {
   pub half_extents: Vec3,
@@ -18,6 +18,36 @@ pub struct CuboidStyle {
   pub corner_material: Option<Material>,
   pub face_material:   Option<Material>,
   pub line_variant:    LineVariant,
+  pub line_pattern:    LinePattern,
+  /// How edges hidden behind the faces are drawn, when the face material is
+  /// [`Material::occluder`].
+  pub hidden_line:     Option<HiddenLineStyle>,
+  /// Background colors for the faces by which way they're facing, if they
+  /// should be tinted.
+  pub face_tints:      Option<CuboidFaceTints>,
+  /// How the faces are shaded by the scene's lights, if they are.
+  pub face_shading:    Option<FaceShading>,
+}
+
+/// Background colors for the faces of a cuboid, by the axis they face along.
+#[derive(Clone, Copy, Debug)]
+pub struct CuboidFaceTints {
+  pub x: Color,
+  pub y: Color,
+  pub z: Color,
+}
+
+impl CuboidFaceTints {
+  /// The tint of the face with the given axis-aligned normal.
+  fn for_normal(&self, normal: Vec3) -> Color {
+    if normal.x != 0.0 {
+      self.x
+    } else if normal.y != 0.0 {
+      self.y
+    } else {
+      self.z
+    }
+  }
 }
 
 impl CuboidStyle {
@@ -26,6 +56,8 @@ impl CuboidStyle {
       material:     self.line_material.clone(),
       cap_material: self.corner_material.clone(),
       variant:      self.line_variant,
+      pattern:      self.line_pattern,
+      hidden:       self.hidden_line.clone(),
     }
   }
 }
@@ -56,6 +88,16 @@ const CUBOID_EDGES: [(usize, usize); 12] = [
   (3, 7),
 ];
 
+/// The outward normals of a cuboid's faces.
+const CUBOID_FACE_NORMALS: [Vec3; 6] = [
+  Vec3::X,
+  Vec3::NEG_X,
+  Vec3::Y,
+  Vec3::NEG_Y,
+  Vec3::Z,
+  Vec3::NEG_Z,
+];
+
 impl DrawnShape for CuboidArgs {
   fn draw(
     &self,
@@ -78,59 +120,45 @@ impl DrawnShape for CuboidArgs {
     });
 
     if let Some(face_material) = style.face_material.clone() {
-      let mut plane_args = PlaneArgs {
-        xy_half_extents: Vec2::ZERO,
-        exclude_borders: true,
-        style:           PlaneStyle {
-          material: face_material,
-        },
-      };
-
-      // front plane
-      plane_args.xy_half_extents = halves.xy();
-      let plane_transform =
-        transform.mul_transform(Transform::from_xyz(0.0, 0.0, halves.y));
-      plane_args.draw(buffer, args, &plane_transform);
-
-      // back plane
-      let plane_transform =
-        transform.mul_transform(Transform::from_xyz(0.0, 0.0, -halves.y));
-      plane_args.draw(buffer, args, &plane_transform);
-
-      // left plane
-      plane_args.xy_half_extents = halves.xz();
-      let plane_transform = transform.mul_transform(
-        Transform::from_xyz(-halves.x, 0.0, 0.0)
-          .with_rotation(Quat::from_axis_angle(Vec3::Y, PI / 2.0)),
-      );
-      plane_args.draw(buffer, args, &plane_transform);
-
-      // right plane
-      let plane_transform = transform.mul_transform(
-        Transform::from_xyz(halves.x, 0.0, 0.0)
-          .with_rotation(Quat::from_axis_angle(Vec3::Y, PI / 2.0)),
-      );
-      plane_args.draw(buffer, args, &plane_transform);
-
-      // top plane
-      plane_args.xy_half_extents = halves.xz();
-      let plane_transform = transform.mul_transform(
-        Transform::from_xyz(0.0, halves.y, 0.0)
-          .with_rotation(Quat::from_axis_angle(Vec3::X, PI / 2.0)),
-      );
-      plane_args.draw(buffer, args, &plane_transform);
-
-      // bottom plane
-      plane_args.xy_half_extents = halves.xz();
-      let plane_transform = transform.mul_transform(
-        Transform::from_xyz(0.0, -halves.y, 0.0)
-          .with_rotation(Quat::from_axis_angle(Vec3::X, PI / 2.0)),
-      );
-      plane_args.draw(buffer, args, &plane_transform);
+      for normal in CUBOID_FACE_NORMALS {
+        // turn a plane (which faces +Z) to face out of this side
+        let rotation = Quat::from_rotation_arc(Vec3::Z, normal);
+        let face_transform = transform.mul_transform(
+          Transform::from_translation(normal * *halves).with_rotation(rotation),
+        );
+        let xy_half_extents = Vec2::new(
+          (rotation * Vec3::X).abs().dot(*halves),
+          (rotation * Vec3::Y).abs().dot(*halves),
+        );
+
+        // skip the faces pointing away from the camera
+        let corners =
+          [Vec2::NEG_ONE, Vec2::new(1.0, -1.0), Vec2::ONE].map(|c| {
+            face_transform.transform_point((c * xy_half_extents).extend(0.0))
+          });
+        if !args.is_front_facing(corners) {
+          continue;
+        }
+
+        let plane_args = PlaneArgs {
+          xy_half_extents,
+          exclude_borders: true,
+          style: PlaneStyle {
+            material: face_material.clone(),
+            tint:     style.face_tints.map(|tints| tints.for_normal(normal)),
+            shading:  style.face_shading.clone(),
+          },
+        };
+        plane_args.draw(buffer, args, &face_transform);
+      }
     }
 
     for line in lines {
       line.draw(buffer, args, transform);
     }
   }
+
+  fn bounds(&self) -> Option<ShapeBounds> {
+    Some(ShapeBounds::new(Vec3::ZERO, self.half_extents))
+  }
 }
@@ -0,0 +1,45 @@
+use bevy::prelude::*;
+
+use super::{
+  CanvasArgs, DrawnShape, HiddenLineStyle, LinePattern, LineVariant, Material,
+  PolylineArgs, PolylineLoopStyle, PolylineStyle, ShapeBuffer,
+};
+
+/// The style of an open curve, like an arc or a Bézier curve.
+#[derive(Clone)]
+pub struct CurveStyle {
+  pub material:         Material,
+  /// The material drawn at both ends of the curve, if any.
+  pub end_cap_material: Option<Material>,
+  pub variant:          LineVariant,
+  pub pattern:          LinePattern,
+  /// How the hidden parts are drawn. See [`HiddenLineStyle`].
+  pub hidden:           Option<HiddenLineStyle>,
+}
+
+impl CurveStyle {
+  /// Draws a curve through local-space `points`, which should be close enough
+  /// together to look smooth.
+  pub(super) fn draw_points(
+    &self,
+    points: Vec<Vec3>,
+    buffer: &mut ShapeBuffer,
+    args: &CanvasArgs,
+    transform: &Transform,
+  ) {
+    PolylineArgs {
+      points,
+      style: PolylineStyle {
+        material:   self.material.clone(),
+        loop_style: PolylineLoopStyle::Open {
+          point_cap_material: None,
+          end_cap_material:   self.end_cap_material.clone(),
+        },
+        variant:    self.variant,
+        pattern:    self.pattern,
+        hidden:     self.hidden.clone(),
+      },
+    }
+    .draw(buffer, args, transform);
+  }
+}
@@ -0,0 +1,99 @@
+use bevy::prelude::*;
+
+use super::{
+  CanvasArgs, DrawnShape, LocalCircle, RoundStyle, ShapeBuffer,
+  draw_circle_arcs, draw_polyline, silhouette_angles,
+};
+use crate::culling::ShapeBounds;
+
+/// A cylinder around the Y axis, centered on the origin. It's drawn as its
+/// silhouette and the visible parts of its rims, with optional rings around
+/// the side facing the camera.
+pub struct CylinderArgs {
+  pub radius:      f32,
+  /// Half of the cylinder's height.
+  pub half_height: f32,
+  /// The number of rings, spaced evenly between the rims.
+  pub rings:       u32,
+  pub style:       RoundStyle,
+}
+
+impl DrawnShape for CylinderArgs {
+  fn draw(
+    &self,
+    buffer: &mut ShapeBuffer,
+    args: &CanvasArgs,
+    transform: &Transform,
+  ) {
+    let CylinderArgs {
+      radius,
+      half_height,
+      rings,
+      style,
+    } = self;
+    let (radius, half_height) = (*radius, *half_height);
+    let eye = args.local_camera_center(transform);
+
+    // how much the side faces the camera at a point on it
+    let side_facing = |point: Vec3| {
+      (point.x * eye.x + point.z * eye.z) / radius - radius * eye.w
+    };
+
+    // the side's silhouette is the two lines whose tangent plane passes
+    // through the camera
+    if let Some(angles) = silhouette_angles(eye, radius * eye.w) {
+      for angle in angles {
+        let (sin, cos) = angle.sin_cos();
+        let bottom = Vec3::new(cos * radius, -half_height, sin * radius);
+        let top = Vec3::new(cos * radius, half_height, sin * radius);
+        draw_polyline(
+          vec![bottom, top],
+          false,
+          &style.material,
+          style.pattern,
+          buffer,
+          args,
+          transform,
+        );
+      }
+    }
+
+    // a rim is all visible when its cap faces the camera, and only where the
+    // side does otherwise
+    for side in [-1.0, 1.0] {
+      let cap_facing = side * eye.y - half_height * eye.w > 0.0;
+      draw_circle_arcs(
+        LocalCircle::around_y(side * half_height, radius),
+        |point| if cap_facing { 1.0 } else { side_facing(point) },
+        &style.material,
+        style.pattern,
+        buffer,
+        args,
+        transform,
+      );
+    }
+
+    let Some(guide_material) = &style.guide_material else {
+      return;
+    };
+    for i in 1..=*rings {
+      let y = half_height * (2.0 * i as f32 / (*rings + 1) as f32 - 1.0);
+      draw_circle_arcs(
+        LocalCircle::around_y(y, radius),
+        side_facing,
+        guide_material,
+        style.guide_pattern,
+        buffer,
+        args,
+        transform,
+      );
+    }
+  }
+
+  fn bounds(&self) -> Option<ShapeBounds> {
+    Some(ShapeBounds::new(
+      Vec3::ZERO,
+      Vec3::new(self.radius, self.half_height, self.radius),
+    ))
+  }
+}
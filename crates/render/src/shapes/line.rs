@@ -1,13 +1,17 @@
 use std::cmp::Ordering;
 
 use bevy::prelude::*;
+use ratatui::symbols::line;
 
 use super::{
   CanvasArgs, DrawnShape, Material, MaterialDrawRequest,
   MaterialDrawRequestType, ProjectedPoint, ShapeBuffer,
-  thin_neighbor::Neighbor,
+  box_neighbor::box_neighbor_symbol,
+  thin_neighbor::{Neighbor, thin_neighbor_symbol},
 };
+use crate::culling::ShapeBounds;
 
+#[derive(Clone)]
 pub struct LineArgs {
   pub from:  Vec3,
   pub to:    Vec3,
@@ -19,11 +23,108 @@ pub struct LineStyle {
   pub material:     Material,
   pub cap_material: Option<Material>,
   pub variant:      LineVariant,
+  pub pattern:      LinePattern,
+  /// How the hidden parts are drawn. See [`HiddenLineStyle`].
+  pub hidden:       Option<HiddenLineStyle>,
+}
+
+/// How a line is drawn where it's hidden behind a [`Material::occluder`], e.g.
+/// dashed or dim for a technical drawing.
+///
+/// Lines, polylines, circles and curves take an `Option<HiddenLineStyle>`.
+/// If it's `None`, the parts of them behind an occluder aren't drawn at all.
+#[derive(Clone)]
+pub struct HiddenLineStyle {
+  pub material: Material,
+  pub pattern:  LinePattern,
+}
+
+/// Which cells along a line are drawn. Lengths are in canvas cells, and
+/// undrawn cells show whatever is behind the line.
+#[derive(Clone, Copy, Debug, Default)]
+pub enum LinePattern {
+  /// Every cell is drawn.
+  #[default]
+  Solid,
+  /// Runs of `dash` drawn cells, separated by `gap` undrawn cells.
+  Dashed { dash: u32, gap: u32 },
+  /// Single drawn cells, separated by `gap` undrawn cells.
+  Dotted { gap: u32 },
+  /// Alternating dashes and single dots, all separated by `gap` undrawn
+  /// cells.
+  DashDot { dash: u32, gap: u32 },
 }
 
-#[derive(Clone, Copy)]
+impl LinePattern {
+  /// Whether the cell `distance` cells along the line is drawn.
+  pub fn is_drawn(&self, distance: u32) -> bool {
+    // worked out in `u64`, so that periods of any lengths can't overflow
+    let distance = distance as u64;
+    match *self {
+      LinePattern::Solid => true,
+      LinePattern::Dashed { dash, gap } => {
+        let (dash, gap) = (dash as u64, gap as u64);
+        let period = dash + gap;
+        period == 0 || distance % period < dash
+      }
+      LinePattern::Dotted { gap } => distance.is_multiple_of(gap as u64 + 1),
+      LinePattern::DashDot { dash, gap } => {
+        let (dash, gap) = (dash as u64, gap as u64);
+        let period = dash + gap + 1 + gap;
+        let phase = distance % period;
+        phase < dash || phase == dash + gap
+      }
+    }
+  }
+}
+
+#[derive(Clone, Copy, Debug)]
 pub enum LineVariant {
+  /// ASCII-ish symbols that follow the line's angle closely.
   Thin,
+  /// Heavy box-drawing symbols (`━`, `┃`, `┏`).
+  Heavy,
+  /// Double box-drawing symbols (`═`, `║`, `╔`).
+  Double,
+  /// Box-drawing symbols with rounded corners (`─`, `│`, `╭`).
+  Rounded,
+}
+
+impl LineVariant {
+  /// Box-drawing symbols only join at right angles, so for those variants a
+  /// missing neighbor (a zero offset at the end of a line) is treated as the
+  /// line carrying straight on, instead of as a turn.
+  pub fn fill_missing_offset(
+    &self,
+    prev: IVec2,
+    next: IVec2,
+  ) -> (IVec2, IVec2) {
+    match self {
+      LineVariant::Thin => (prev, next),
+      LineVariant::Heavy | LineVariant::Double | LineVariant::Rounded => {
+        match (prev == IVec2::ZERO, next == IVec2::ZERO) {
+          (true, false) => (-next, next),
+          (false, true) => (prev, -prev),
+          _ => (prev, next),
+        }
+      }
+    }
+  }
+
+  /// Picks the symbol for a cell of a line whose neighbors are in the
+  /// directions `prev` and `next`.
+  pub fn neighbor_symbol(
+    &self,
+    prev: Neighbor,
+    next: Neighbor,
+  ) -> &'static str {
+    match self {
+      LineVariant::Thin => thin_neighbor_symbol(prev, next),
+      LineVariant::Heavy => box_neighbor_symbol(prev, next, &line::THICK),
+      LineVariant::Double => box_neighbor_symbol(prev, next, &line::DOUBLE),
+      LineVariant::Rounded => box_neighbor_symbol(prev, next, &line::ROUNDED),
+    }
+  }
 }
 
 impl DrawnShape for LineArgs {
@@ -38,52 +139,81 @@ impl DrawnShape for LineArgs {
     let transformed_from = transform.transform_point(*from);
     let transformed_to = transform.transform_point(*to);
 
-    let canvas_from = args.world_to_canvas_coords(transformed_from);
-    let canvas_to = args.world_to_canvas_coords(transformed_to);
-
-    let points = match style.variant {
-      LineVariant::Thin => basic_8_connected(canvas_from, canvas_to),
+    let Some(segment) = args.clip_segment(transformed_from, transformed_to)
+    else {
+      return;
     };
 
+    let points = basic_8_connected(segment.from, segment.to);
+
     for (i, p) in points.iter().enumerate() {
+      // are we on the end cap (ends moved by clipping aren't real ends)
+      let is_end = (i == 0 && !segment.from_clipped)
+        || (i == points.len() - 1 && !segment.to_clipped);
+      let is_cap = is_end && style.cap_material.is_some();
+
+      // leave gaps in the pattern undrawn, counting from the real start so
+      // the pattern doesn't move when the line is clipped
+      let distance = segment.skipped_cells.saturating_add(i as u32);
+      if !is_cap && !style.pattern.is_drawn(distance) {
+        continue;
+      }
+
       // get the previous and next neighbors, using self if at extent
       let next_point_index = (i + 1).min(points.len() - 1);
       let next_point = points[next_point_index].pos();
       let prev_point = points[i.saturating_sub(1)].pos();
       let next_point_offset = next_point - p.pos();
       let prev_point_offset = prev_point - p.pos();
+      let (prev_point_offset, next_point_offset) = style
+        .variant
+        .fill_missing_offset(prev_point_offset, next_point_offset);
       let prev_neighbor =
         Neighbor::find(prev_point_offset, args.character_aspect_ratio());
       let next_neighbor =
         Neighbor::find(next_point_offset, args.character_aspect_ratio());
 
-      // are we on the end cap
-      let is_end = i == 0 || i == points.len() - 1;
-
       // select the material for this cell
       let material = match style.cap_material.clone() {
         Some(cap_mat) if is_end => cap_mat,
         _ => style.material.clone(),
       };
 
-      // figure out what info we need
-      let mat_request_type = material.draw_request_type();
-
-      // fill in the info
-      let request = match mat_request_type {
+      // figure out what info each material needs, and fill it in
+      let request_for = |material: &Material| match material.draw_request_type()
+      {
         MaterialDrawRequestType::None => MaterialDrawRequest::None,
         MaterialDrawRequestType::Neighbors => MaterialDrawRequest::Neighbors {
-          prev: prev_neighbor,
-          next: next_neighbor,
+          prev:    prev_neighbor,
+          next:    next_neighbor,
+          variant: style.variant,
         },
       };
 
-      // determine the character
-      let drawn_material = material.draw(request, p.depth());
+      // determine the character, and how it's drawn if it's hidden
+      let Some(mut drawn_material) =
+        material.draw_or_warn(request_for(&material), p.depth())
+      else {
+        continue;
+      };
+      if let Some(hidden) = style
+        .hidden
+        .as_ref()
+        .filter(|h| h.pattern.is_drawn(distance))
+        .and_then(|h| {
+          h.material.draw_or_warn(request_for(&h.material), p.depth())
+        })
+      {
+        drawn_material = drawn_material.with_hidden(hidden);
+      }
 
       buffer.draw(drawn_material, *p);
     }
   }
+
+  fn bounds(&self) -> Option<ShapeBounds> {
+    Some(ShapeBounds::from_points([self.from, self.to]))
+  }
 }
 
 pub fn basic_8_connected(
@@ -130,3 +260,68 @@ pub fn basic_8_connected(
 
   result
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  /// Which of the cells `from..to` along a line are drawn, as `#` and `.`.
+  fn drawn_cells(pattern: LinePattern, from: u32, to: u32) -> String {
+    (from..to)
+      .map(|d| if pattern.is_drawn(d) { '#' } else { '.' })
+      .collect()
+  }
+
+  #[test]
+  fn patterns_repeat_with_their_period() {
+    let dashed = LinePattern::Dashed { dash: 3, gap: 2 };
+    assert_eq!(drawn_cells(dashed, 0, 12), "###..###..##");
+    let dotted = LinePattern::Dotted { gap: 2 };
+    assert_eq!(drawn_cells(dotted, 0, 10), "#..#..#..#");
+    let dash_dot = LinePattern::DashDot { dash: 3, gap: 1 };
+    assert_eq!(drawn_cells(dash_dot, 0, 14), "###.#.###.#.##");
+  }
+
+  #[test]
+  fn patterns_keep_their_phase_across_segments() {
+    // a polyline's second segment carries on from the distance the first one
+    // ended at, so its cells should follow on from the first's
+    for pattern in [
+      LinePattern::Dashed { dash: 3, gap: 2 },
+      LinePattern::Dotted { gap: 2 },
+      LinePattern::DashDot { dash: 3, gap: 1 },
+    ] {
+      let whole = drawn_cells(pattern, 0, 20);
+      let split = drawn_cells(pattern, 0, 7) + &drawn_cells(pattern, 7, 20);
+      assert_eq!(whole, split);
+    }
+  }
+
+  #[test]
+  fn huge_patterns_dont_overflow() {
+    let dashed = LinePattern::Dashed {
+      dash: u32::MAX,
+      gap:  u32::MAX,
+    };
+    assert!(dashed.is_drawn(0));
+    assert!(dashed.is_drawn(u32::MAX - 1));
+    assert!(!dashed.is_drawn(u32::MAX));
+
+    let dotted = LinePattern::Dotted { gap: u32::MAX };
+    assert!(dotted.is_drawn(0));
+    assert!(!dotted.is_drawn(u32::MAX));
+
+    let dash_dot = LinePattern::DashDot {
+      dash: u32::MAX,
+      gap:  u32::MAX,
+    };
+    assert!(dash_dot.is_drawn(0));
+    assert!(!dash_dot.is_drawn(u32::MAX));
+  }
+
+  #[test]
+  fn empty_patterns_draw_everything() {
+    let dashed = LinePattern::Dashed { dash: 0, gap: 0 };
+    assert!((0..4).all(|d| dashed.is_drawn(d)));
+  }
+}
@@ -1,124 +1,221 @@
-use colors::{
-  BASE_COLOR_RATATUI, DIM_TEXT_COLOR_RATATUI, LINEART_COLOR_RATATUI,
-};
+use std::{fmt, sync::Arc};
+
+use bevy::{prelude::*, utils::HashMap};
 use ratatui::{buffer::Cell, style::Color};
 use smol_str::SmolStr;
 
-use super::thin_neighbor::{Neighbor, thin_neighbor_symbol};
+use super::{LineVariant, thin_neighbor::Neighbor};
+use crate::theme::Theme;
 
-fn blend_u8_value(from: u8, to: u8, t: f32) -> u8 {
-  (from as f32 + (to as f32 - from as f32) * t).round() as u8
-}
+/// How a material looks: which symbol it draws in each cell, and how that
+/// cell is rendered against whatever is behind it.
+///
+/// Implement this to define a material outside of this crate, and wrap it
+/// with [`Material::new`] to draw shapes with it.
+pub trait MaterialKind: fmt::Debug + Send + Sync + 'static {
+  /// The kind of information this material needs to draw itself.
+  fn draw_request_type(&self) -> MaterialDrawRequestType {
+    MaterialDrawRequestType::None
+  }
 
-fn blend_color(from: Color, to: Color, t: f32) -> Color {
-  let t = t.clamp(0.0, 1.0);
-  match (from, to) {
-    (Color::Rgb(fr, fg, fb), Color::Rgb(tr, tg, tb)) => Color::Rgb(
-      blend_u8_value(fr, tr, t),
-      blend_u8_value(fg, tg, t),
-      blend_u8_value(fb, tb, t),
-    ),
-    (a, b) => {
-      panic!("failed to blend colors, not RGB values: a = {a:?}, b = {b:?}");
-    }
+  /// Picks the symbol drawn in a cell, from the information asked for by
+  /// [`draw_request_type`](Self::draw_request_type).
+  fn symbol(
+    &self,
+    draw_request: &MaterialDrawRequest,
+  ) -> Result<SmolStr, MaterialError>;
+
+  /// Renders a drawn cell of this material in `theme`'s colors, given the
+  /// cell behind it if there is one.
+  fn render(
+    &self,
+    drawn: &DrawnMaterial,
+    behind: Option<&DrawnMaterial>,
+    theme: &Theme,
+  ) -> Cell;
+
+  /// The color this material's symbol is shown in when it's behind a face
+  /// that lets it show through, or `None` if it doesn't show.
+  fn show_through_color(&self, theme: &Theme) -> Option<Color> {
+    Some(theme.dim_text)
   }
+
+  /// Whether this hides what's behind it without drawing anything.
+  fn is_occluder(&self) -> bool { false }
+
+  /// Whether cameras fade this with depth when their
+  /// [`DepthCue`](crate::depth_cue::DepthCue) only fades lineart.
+  fn is_depth_cued(&self) -> bool { false }
+
+  /// Whether this is a glyph that has to be shown as-is, like text, rather
+  /// than a stroke that can be drawn as sub-cell dots.
+  fn is_glyph(&self) -> bool { false }
 }
 
-/// A material descriptor.
-#[allow(clippy::enum_variant_names, dead_code)]
+/// A material descriptor, which is cheap to clone.
 #[derive(Clone, Debug)]
-pub enum Material {
-  Test,
-  WallFace,
-  WallEdge,
-  WallCorner,
-  ColoredEdge(Color),
-  ColoredPoint(Color),
-  Text {
-    text:     SmolStr,
-    fg_color: Color,
-    bg_color: Color,
-  },
-}
+pub struct Material(Arc<dyn MaterialKind>);
 
 impl Material {
+  /// Wraps a material defined outside of this crate.
+  pub fn new(kind: impl MaterialKind) -> Self { Material(Arc::new(kind)) }
+
+  /// `#` on the base background, for debugging.
+  pub fn test() -> Self { Self::new(Test) }
+
+  /// A blank face, which shows what's behind it dimmed.
+  pub fn wall_face() -> Self { Self::new(WallFace) }
+
+  /// A line that fades into the background with depth.
+  pub fn wall_edge() -> Self { Self::new(WallEdge) }
+
+  /// A point where lines meet, which fades into the background with depth.
+  pub fn wall_corner() -> Self { Self::new(WallCorner) }
+
+  /// A face that hides what's behind it without drawing anything itself,
+  /// other than the hidden parts of lines that have a hidden style.
+  pub fn occluder() -> Self { Self::new(Occluder) }
+
+  /// A line in a single color.
+  pub fn colored_edge(color: Color) -> Self { Self::new(ColoredEdge(color)) }
+
+  /// A point in a single color.
+  pub fn colored_point(color: Color) -> Self { Self::new(ColoredPoint(color)) }
+
+  /// A cell of text, drawn as-is.
+  pub fn text(text: SmolStr, fg_color: Color, bg_color: Color) -> Self {
+    Self::new(Text {
+      text,
+      fg_color,
+      bg_color,
+    })
+  }
+
+  /// What this material does.
+  pub fn kind(&self) -> &dyn MaterialKind { self.0.as_ref() }
+
   pub fn draw_request_type(&self) -> MaterialDrawRequestType {
-    match self {
-      Material::Test => MaterialDrawRequestType::None,
-      Material::WallFace => MaterialDrawRequestType::None,
-      Material::WallEdge => MaterialDrawRequestType::Neighbors,
-      Material::WallCorner => MaterialDrawRequestType::None,
-      Material::ColoredEdge(_) => MaterialDrawRequestType::Neighbors,
-      Material::ColoredPoint(_) => MaterialDrawRequestType::None,
-      Material::Text { .. } => MaterialDrawRequestType::None,
-    }
+    self.0.draw_request_type()
   }
 
+  /// Draws a cell of this material, or returns an error if `draw_request`
+  /// doesn't have the information it needs.
   pub fn draw(
     &self,
     draw_request: MaterialDrawRequest,
     proj_depth: f32,
-  ) -> DrawnMaterial {
-    match (self, draw_request) {
-      (Material::Test, _) => DrawnMaterial {
-        mat: Material::Test,
-        sym: "#".into(),
-        proj_depth,
-      },
-      (Material::WallFace, _) => DrawnMaterial {
-        mat: Material::WallFace,
-        sym: " ".into(),
-        proj_depth,
-      },
-      (Material::WallEdge, MaterialDrawRequest::Neighbors { prev, next }) => {
-        DrawnMaterial {
-          mat: Material::WallEdge,
-          sym: thin_neighbor_symbol(prev, next).into(),
-          proj_depth,
-        }
-      }
-      (Material::WallCorner, _) => DrawnMaterial {
-        mat: Material::WallCorner,
-        sym: "•".into(),
-        proj_depth,
-      },
-      (
-        Material::ColoredEdge(color),
-        MaterialDrawRequest::Neighbors { prev, next },
-      ) => DrawnMaterial {
-        mat: Material::ColoredEdge(*color),
-        sym: thin_neighbor_symbol(prev, next).into(),
-        proj_depth,
-      },
-      (Material::ColoredPoint(color), _) => DrawnMaterial {
-        mat: Material::ColoredPoint(*color),
-        sym: "•".into(),
-        proj_depth,
-      },
-      (
-        Material::Text {
-          text,
-          fg_color,
-          bg_color,
-        },
-        _,
-      ) => DrawnMaterial {
-        mat: Material::Text {
-          text:     text.clone(),
-          fg_color: *fg_color,
-          bg_color: *bg_color,
-        },
-        sym: text.clone(),
-        proj_depth,
-      },
-      (mat, req) => panic!(
-        "material/draw_request mismatch: got material {mat:?}, draw_request \
-         {req:?}"
+  ) -> Result<DrawnMaterial, MaterialError> {
+    Ok(DrawnMaterial {
+      mat: self.clone(),
+      sym: self.0.symbol(&draw_request)?,
+      proj_depth,
+      hidden: None,
+      tint: None,
+      stroke: None,
+    })
+  }
+
+  /// Like [`draw`](Self::draw), but logs the error (once) and returns `None`
+  /// instead, for shapes that can't do anything else with it.
+  pub(super) fn draw_or_warn(
+    &self,
+    draw_request: MaterialDrawRequest,
+    proj_depth: f32,
+  ) -> Option<DrawnMaterial> {
+    self
+      .draw(draw_request, proj_depth)
+      .inspect_err(|e| warn_once!("skipping cells that can't be drawn: {e}"))
+      .ok()
+  }
+}
+
+/// An error from drawing a material.
+#[derive(Clone, Debug)]
+pub enum MaterialError {
+  /// The draw request doesn't have the information the material needs, like
+  /// a line material drawn as a face.
+  RequestMismatch {
+    material: String,
+    request:  MaterialDrawRequest,
+  },
+  /// No material is registered under the name.
+  UnknownMaterial(String),
+}
+
+impl MaterialError {
+  /// The error for `material` getting a request it can't draw with.
+  pub fn request_mismatch(
+    material: &dyn MaterialKind,
+    request: &MaterialDrawRequest,
+  ) -> Self {
+    MaterialError::RequestMismatch {
+      material: format!("{material:?}"),
+      request:  request.clone(),
+    }
+  }
+}
+
+impl fmt::Display for MaterialError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      MaterialError::RequestMismatch { material, request } => write!(
+        f,
+        "material/draw_request mismatch: got material {material}, \
+         draw_request {request:?}"
       ),
+      MaterialError::UnknownMaterial(name) => {
+        write!(f, "no material is registered as `{name}`")
+      }
     }
   }
 }
 
+impl std::error::Error for MaterialError {}
+
+/// Materials by name, so they can be picked from data. The built-in
+/// materials that don't take any parameters are registered from the start.
+#[derive(Resource, Clone, Debug)]
+pub struct MaterialRegistry {
+  materials: HashMap<String, Material>,
+}
+
+impl Default for MaterialRegistry {
+  fn default() -> Self {
+    let mut registry = MaterialRegistry {
+      materials: HashMap::default(),
+    };
+    registry
+      .register("test", Material::test())
+      .register("wall_face", Material::wall_face())
+      .register("wall_edge", Material::wall_edge())
+      .register("wall_corner", Material::wall_corner())
+      .register("occluder", Material::occluder());
+    registry
+  }
+}
+
+impl MaterialRegistry {
+  /// Registers `material` as `name`, replacing whatever was registered as it
+  /// before.
+  pub fn register(
+    &mut self,
+    name: impl Into<String>,
+    material: Material,
+  ) -> &mut Self {
+    self.materials.insert(name.into(), material);
+    self
+  }
+
+  /// The material registered as `name`.
+  pub fn get(&self, name: &str) -> Result<Material, MaterialError> {
+    self
+      .materials
+      .get(name)
+      .cloned()
+      .ok_or_else(|| MaterialError::UnknownMaterial(name.to_string()))
+  }
+}
+
 /// The kind of information a given material variant needs to draw itself.
 #[derive(Clone, Debug)]
 pub enum MaterialDrawRequestType {
@@ -133,8 +230,13 @@ pub enum MaterialDrawRequestType {
 pub enum MaterialDrawRequest {
   /// No additional information needed.
   None,
-  /// The previous and next neighbor directions for this cell.
-  Neighbors { prev: Neighbor, next: Neighbor },
+  /// The previous and next neighbor directions for this cell, and the
+  /// variant of line it's part of.
+  Neighbors {
+    prev:    Neighbor,
+    next:    Neighbor,
+    variant: LineVariant,
+  },
 }
 
 /// A material whose stroke has been determined.
@@ -143,113 +245,318 @@ pub struct DrawnMaterial {
   mat:        Material,
   sym:        SmolStr,
   proj_depth: f32,
+  /// How this is drawn when it's behind an occluder, if it's drawn at all.
+  hidden:     Option<Box<DrawnMaterial>>,
+  /// The background color this is drawn with instead of its own, if any.
+  tint:       Option<Color>,
+  /// The color its symbol should be drawn in instead of its own, if any.
+  stroke:     Option<Color>,
 }
 
 impl DrawnMaterial {
-  pub fn render(&self, behind: Option<&Self>) -> Cell {
-    let DrawnMaterial {
-      mat,
-      sym,
-      proj_depth,
-    } = self;
-
-    match mat {
-      Material::Test => {
-        let mut cell = Cell::default();
-        cell.set_symbol(sym);
-        cell.set_bg(BASE_COLOR_RATATUI);
-        cell.set_fg(LINEART_COLOR_RATATUI);
-        cell
-      }
-      Material::WallFace => match behind {
-        Some(DrawnMaterial {
-          mat: behind_mat,
-          sym: behind_symbol,
-          ..
-        }) => match behind_mat {
-          Material::WallFace => {
-            let mut cell = Cell::default();
-            cell.set_symbol(sym);
-            cell.set_bg(BASE_COLOR_RATATUI);
-            cell
-          }
-          Material::Test
-          | Material::WallEdge
-          | Material::WallCorner
-          | Material::Text { .. } => {
-            let mut cell = Cell::default();
-            cell.set_symbol(behind_symbol);
-            cell.set_bg(BASE_COLOR_RATATUI);
-            cell.set_fg(DIM_TEXT_COLOR_RATATUI);
-            cell
-          }
-          Material::ColoredEdge(edge_color) => {
-            let mut cell = Cell::default();
-            cell.set_symbol(behind_symbol);
-            cell.set_bg(BASE_COLOR_RATATUI);
-            cell.set_fg(*edge_color);
-            cell
-          }
-          Material::ColoredPoint(point_color) => {
-            let mut cell = Cell::default();
-            cell.set_symbol(behind_symbol);
-            cell.set_bg(BASE_COLOR_RATATUI);
-            cell.set_fg(*point_color);
-            cell
-          }
-        },
-        None => {
-          let mut cell = Cell::default();
-          cell.set_symbol(sym);
-          cell.set_bg(BASE_COLOR_RATATUI);
-          cell
-        }
-      },
-      Material::WallEdge => {
-        let mut cell = Cell::default();
-        cell.set_symbol(sym);
-        cell.set_bg(BASE_COLOR_RATATUI);
-        cell.set_fg(blend_color(
-          LINEART_COLOR_RATATUI,
-          BASE_COLOR_RATATUI,
-          *proj_depth,
-        ));
-        cell
-      }
-      Material::WallCorner => {
-        let mut cell = Cell::default();
-        cell.set_symbol(sym);
-        cell.set_bg(BASE_COLOR_RATATUI);
-        cell.set_fg(blend_color(
-          LINEART_COLOR_RATATUI,
-          BASE_COLOR_RATATUI,
-          *proj_depth,
-        ));
-        cell
-      }
-      Material::ColoredEdge(color) => {
-        let mut cell = Cell::default();
-        cell.set_symbol(sym);
-        cell.set_bg(BASE_COLOR_RATATUI);
-        cell.set_fg(*color);
-        cell
-      }
-      Material::ColoredPoint(color) => {
-        let mut cell = Cell::default();
-        cell.set_symbol(sym);
-        cell.set_bg(BASE_COLOR_RATATUI);
-        cell.set_fg(*color);
+  /// Sets how this is drawn when it's hidden behind an occluder. Without this,
+  /// it's not drawn at all there.
+  pub fn with_hidden(self, hidden: DrawnMaterial) -> Self {
+    Self {
+      hidden: Some(Box::new(hidden)),
+      ..self
+    }
+  }
+
+  /// Sets the background color this is drawn with, e.g. to shade a face by
+  /// which way it's facing.
+  pub fn with_tint(self, tint: Color) -> Self {
+    Self {
+      tint: Some(tint),
+      ..self
+    }
+  }
+
+  /// Swaps the symbol the material picked, e.g. for one from a shading ramp.
+  pub fn with_symbol(self, sym: SmolStr) -> Self { Self { sym, ..self } }
+
+  /// Sets the color the symbol should be drawn in, e.g. to shade a lit face.
+  /// It's up to the material to use it.
+  pub fn with_stroke(self, stroke: Color) -> Self {
+    Self {
+      stroke: Some(stroke),
+      ..self
+    }
+  }
+
+  /// The material this was drawn with.
+  pub fn material(&self) -> &Material { &self.mat }
+
+  /// The symbol the material picked for this cell.
+  pub fn symbol(&self) -> &str { &self.sym }
+
+  /// The projected depth this was drawn at.
+  pub fn proj_depth(&self) -> f32 { self.proj_depth }
+
+  /// The color the symbol should be drawn in, if it was given one.
+  pub fn stroke(&self) -> Option<Color> { self.stroke }
+
+  /// How this is drawn when it's behind an occluder, if it's drawn at all.
+  pub fn hidden(&self) -> Option<&DrawnMaterial> { self.hidden.as_deref() }
+
+  /// Whether this hides what's behind it without drawing anything.
+  pub fn is_occluder(&self) -> bool { self.mat.kind().is_occluder() }
+
+  /// Whether this is a glyph that has to be shown as-is, like text, rather
+  /// than a stroke that can be drawn as sub-cell dots.
+  pub fn is_glyph(&self) -> bool { self.mat.kind().is_glyph() }
+
+  /// Whether this draws nothing on its own, like a face that only hides what's
+  /// behind it.
+  pub fn is_blank(&self) -> bool { self.sym.trim().is_empty() }
+
+  pub fn render(&self, behind: Option<&Self>, theme: &Theme) -> Cell {
+    let mut cell = self.mat.kind().render(self, behind, theme);
+    if let Some(tint) = self.tint {
+      cell.set_bg(tint);
+    }
+    cell
+  }
+}
+
+/// A cell showing `symbol` on the theme's base background.
+fn base_cell(symbol: &str, theme: &Theme) -> Cell {
+  let mut cell = Cell::default();
+  cell.set_symbol(symbol);
+  cell.set_bg(theme.base);
+  cell
+}
+
+/// A face's own cell, showing its symbol (blank unless it's shaded) in its
+/// stroke color, or the lineart color without one.
+fn face_cell(drawn: &DrawnMaterial, theme: &Theme) -> Cell {
+  let mut cell = base_cell(drawn.symbol(), theme);
+  cell.set_fg(drawn.stroke().unwrap_or(theme.lineart));
+  cell
+}
+
+/// The symbol of a line material, which needs its neighbors.
+fn line_symbol(
+  material: &dyn MaterialKind,
+  draw_request: &MaterialDrawRequest,
+) -> Result<SmolStr, MaterialError> {
+  match draw_request {
+    MaterialDrawRequest::Neighbors {
+      prev,
+      next,
+      variant,
+    } => Ok(variant.neighbor_symbol(*prev, *next).into()),
+    MaterialDrawRequest::None => {
+      Err(MaterialError::request_mismatch(material, draw_request))
+    }
+  }
+}
+
+#[derive(Debug)]
+struct Test;
+
+impl MaterialKind for Test {
+  fn symbol(&self, _: &MaterialDrawRequest) -> Result<SmolStr, MaterialError> {
+    Ok("#".into())
+  }
+
+  fn render(
+    &self,
+    drawn: &DrawnMaterial,
+    _: Option<&DrawnMaterial>,
+    theme: &Theme,
+  ) -> Cell {
+    let mut cell = base_cell(drawn.symbol(), theme);
+    cell.set_fg(theme.lineart);
+    cell
+  }
+}
+
+#[derive(Debug)]
+struct WallFace;
+
+impl MaterialKind for WallFace {
+  fn symbol(&self, _: &MaterialDrawRequest) -> Result<SmolStr, MaterialError> {
+    Ok(" ".into())
+  }
+
+  fn render(
+    &self,
+    drawn: &DrawnMaterial,
+    behind: Option<&DrawnMaterial>,
+    theme: &Theme,
+  ) -> Cell {
+    let shown_through = behind.and_then(|behind| {
+      Some((behind, behind.material().kind().show_through_color(theme)?))
+    });
+    match shown_through {
+      Some((behind, color)) => {
+        let mut cell = base_cell(behind.symbol(), theme);
+        cell.set_fg(color);
         cell
       }
-      Material::Text {
-        fg_color, bg_color, ..
-      } => {
-        let mut cell = Cell::default();
-        cell.set_symbol(sym);
-        cell.set_bg(*bg_color);
-        cell.set_fg(*fg_color);
+      None => face_cell(drawn, theme),
+    }
+  }
+
+  fn show_through_color(&self, _: &Theme) -> Option<Color> { None }
+}
+
+#[derive(Debug)]
+struct WallEdge;
+
+impl MaterialKind for WallEdge {
+  fn draw_request_type(&self) -> MaterialDrawRequestType {
+    MaterialDrawRequestType::Neighbors
+  }
+
+  fn symbol(
+    &self,
+    draw_request: &MaterialDrawRequest,
+  ) -> Result<SmolStr, MaterialError> {
+    line_symbol(self, draw_request)
+  }
+
+  fn render(
+    &self,
+    drawn: &DrawnMaterial,
+    _: Option<&DrawnMaterial>,
+    theme: &Theme,
+  ) -> Cell {
+    let mut cell = base_cell(drawn.symbol(), theme);
+    cell.set_fg(theme.lineart);
+    cell
+  }
+
+  fn is_depth_cued(&self) -> bool { true }
+}
+
+#[derive(Debug)]
+struct WallCorner;
+
+impl MaterialKind for WallCorner {
+  fn symbol(&self, _: &MaterialDrawRequest) -> Result<SmolStr, MaterialError> {
+    Ok("•".into())
+  }
+
+  fn render(
+    &self,
+    drawn: &DrawnMaterial,
+    _: Option<&DrawnMaterial>,
+    theme: &Theme,
+  ) -> Cell {
+    let mut cell = base_cell(drawn.symbol(), theme);
+    cell.set_fg(theme.lineart);
+    cell
+  }
+
+  fn is_depth_cued(&self) -> bool { true }
+}
+
+#[derive(Debug)]
+struct Occluder;
+
+impl MaterialKind for Occluder {
+  fn symbol(&self, _: &MaterialDrawRequest) -> Result<SmolStr, MaterialError> {
+    Ok(" ".into())
+  }
+
+  fn render(
+    &self,
+    drawn: &DrawnMaterial,
+    behind: Option<&DrawnMaterial>,
+    theme: &Theme,
+  ) -> Cell {
+    match behind.and_then(DrawnMaterial::hidden) {
+      Some(hidden) => {
+        let mut cell = hidden.render(None, theme);
+        cell.set_bg(theme.base);
         cell
       }
+      None => face_cell(drawn, theme),
     }
   }
+
+  fn show_through_color(&self, _: &Theme) -> Option<Color> { None }
+
+  fn is_occluder(&self) -> bool { true }
+}
+
+#[derive(Debug)]
+struct ColoredEdge(Color);
+
+impl MaterialKind for ColoredEdge {
+  fn draw_request_type(&self) -> MaterialDrawRequestType {
+    MaterialDrawRequestType::Neighbors
+  }
+
+  fn symbol(
+    &self,
+    draw_request: &MaterialDrawRequest,
+  ) -> Result<SmolStr, MaterialError> {
+    line_symbol(self, draw_request)
+  }
+
+  fn render(
+    &self,
+    drawn: &DrawnMaterial,
+    _: Option<&DrawnMaterial>,
+    theme: &Theme,
+  ) -> Cell {
+    let mut cell = base_cell(drawn.symbol(), theme);
+    cell.set_fg(self.0);
+    cell
+  }
+
+  fn show_through_color(&self, _: &Theme) -> Option<Color> { Some(self.0) }
+}
+
+#[derive(Debug)]
+struct ColoredPoint(Color);
+
+impl MaterialKind for ColoredPoint {
+  fn symbol(&self, _: &MaterialDrawRequest) -> Result<SmolStr, MaterialError> {
+    Ok("•".into())
+  }
+
+  fn render(
+    &self,
+    drawn: &DrawnMaterial,
+    _: Option<&DrawnMaterial>,
+    theme: &Theme,
+  ) -> Cell {
+    let mut cell = base_cell(drawn.symbol(), theme);
+    cell.set_fg(self.0);
+    cell
+  }
+
+  fn show_through_color(&self, _: &Theme) -> Option<Color> { Some(self.0) }
+}
+
+#[derive(Debug)]
+struct Text {
+  text:     SmolStr,
+  fg_color: Color,
+  bg_color: Color,
+}
+
+impl MaterialKind for Text {
+  fn symbol(&self, _: &MaterialDrawRequest) -> Result<SmolStr, MaterialError> {
+    Ok(self.text.clone())
+  }
+
+  fn render(
+    &self,
+    drawn: &DrawnMaterial,
+    _: Option<&DrawnMaterial>,
+    _: &Theme,
+  ) -> Cell {
+    let mut cell = Cell::default();
+    cell.set_symbol(drawn.symbol());
+    cell.set_bg(self.bg_color);
+    cell.set_fg(self.fg_color);
+    cell
+  }
+
+  fn is_glyph(&self) -> bool { true }
 }
@@ -0,0 +1,438 @@
+use bevy::{prelude::*, utils::HashMap};
+
+use super::{
+  CanvasArgs, DrawnShape, FaceShading, LineStyle, Material, PlaneStyle,
+  ProjectedPoint, ShapeBuffer, line::LineArgs, plane::face_depth_bias,
+};
+use crate::culling::ShapeBounds;
+
+/// A triangle mesh, with filled faces and an optional wireframe along its
+/// feature edges.
+pub struct MeshArgs {
+  /// The local-space positions of the mesh's vertices.
+  pub vertices:      Vec<Vec3>,
+  /// Triangles, as indices into `vertices`.
+  pub triangles:     Vec<[usize; 3]>,
+  /// The edges drawn as lines, as pairs of indices into `vertices`.
+  pub feature_edges: Vec<(usize, usize)>,
+  pub style:         MeshStyle,
+}
+
+#[derive(Clone)]
+pub struct MeshStyle {
+  /// The material the triangles are filled with, if they're filled.
+  pub face_material: Option<Material>,
+  /// How the triangles are shaded by the scene's lights, if they are. They're
+  /// lit on the side their vertices wind counter-clockwise around.
+  pub face_shading:  Option<FaceShading>,
+  /// The style of the feature edges, if they're drawn.
+  pub edge_style:    Option<LineStyle>,
+}
+
+/// A vertex of a triangle being rasterized.
+#[derive(Clone, Copy)]
+struct RasterVertex {
+  /// The position on the canvas, not snapped to a cell.
+  position:     Vec2,
+  /// `1 / w`, which is linear across the canvas.
+  inv_w:        f32,
+  /// `depth / w`, which is linear across the canvas.
+  depth_over_w: f32,
+}
+
+impl DrawnShape for MeshArgs {
+  fn draw(
+    &self,
+    buffer: &mut ShapeBuffer,
+    args: &CanvasArgs,
+    transform: &Transform,
+  ) {
+    draw_mesh(
+      &self.vertices,
+      &self.triangles,
+      &self.feature_edges,
+      &self.style,
+      buffer,
+      args,
+      transform,
+    );
+  }
+
+  fn bounds(&self) -> Option<ShapeBounds> {
+    Some(ShapeBounds::from_points(self.vertices.iter().copied()))
+  }
+}
+
+/// Draws a mesh from borrowed parts, for shapes that keep their geometry
+/// elsewhere (like in an asset).
+pub(crate) fn draw_mesh(
+  vertices: &[Vec3],
+  triangles: &[[usize; 3]],
+  feature_edges: &[(usize, usize)],
+  style: &MeshStyle,
+  buffer: &mut ShapeBuffer,
+  args: &CanvasArgs,
+  transform: &Transform,
+) {
+  if let Some(face_material) = &style.face_material {
+    let face_style = PlaneStyle {
+      material: face_material.clone(),
+      tint:     None,
+      shading:  style.face_shading.clone(),
+    };
+    fill_triangles(vertices, triangles, &face_style, buffer, args, transform);
+  }
+
+  if let Some(edge_style) = &style.edge_style {
+    for &(i, j) in feature_edges {
+      if !indices_in_range(&[i, j], vertices.len()) {
+        continue;
+      }
+      LineArgs {
+        from:  vertices[i],
+        to:    vertices[j],
+        style: edge_style.clone(),
+      }
+      .draw(buffer, args, transform);
+    }
+  }
+}
+
+/// Whether every index in `indices` is one of `vertex_count` vertices. Meshes
+/// can be built by hand, so bad indices are logged (once) and skipped rather
+/// than panicking mid-frame.
+fn indices_in_range(indices: &[usize], vertex_count: usize) -> bool {
+  let in_range = indices.iter().all(|&i| i < vertex_count);
+  if !in_range {
+    warn_once!(
+      "skipping mesh triangles or edges with vertex indices out of range: \
+       {indices:?} with {vertex_count} vertices"
+    );
+  }
+  in_range
+}
+
+/// Fills triangles of local-space vertices with a face style, shading each
+/// triangle by the light falling on it if the style asks for it.
+pub(super) fn fill_triangles(
+  vertices: &[Vec3],
+  triangles: &[[usize; 3]],
+  style: &PlaneStyle,
+  buffer: &mut ShapeBuffer,
+  args: &CanvasArgs,
+  transform: &Transform,
+) {
+  let world_vertices = vertices
+    .iter()
+    .map(|v| transform.transform_point(*v))
+    .collect::<Vec<_>>();
+  let view_vertices = world_vertices
+    .iter()
+    .map(|v| args.world_to_view(*v))
+    .collect::<Vec<_>>();
+
+  let project = |point: Vec3| {
+    let inv_w = 1.0 / args.view_clip_w(point);
+    RasterVertex {
+      position: args.view_to_canvas_position(point),
+      inv_w,
+      depth_over_w: args.view_depth(point) * inv_w,
+    }
+  };
+
+  for triangle in triangles {
+    if !indices_in_range(triangle, vertices.len()) {
+      continue;
+    }
+    let polygon = clip_to_depth_range(args, triangle.map(|i| view_vertices[i]));
+    let Some((&first, rest)) = polygon.split_first() else {
+      continue;
+    };
+
+    let [a, b, c] = triangle.map(|i| world_vertices[i]);
+    let intensity =
+      style.face_intensity(args, (a + b + c) / 3.0, (b - a).cross(c - a));
+
+    // clipping leaves a convex polygon, so it can be drawn as a fan
+    let first = project(first);
+    for pair in rest.windows(2) {
+      let vertices = [first, project(pair[0]), project(pair[1])];
+      let depth_bias = face_depth_bias(
+        vertices.map(|v| (v.position, v.depth_over_w / v.inv_w)),
+      );
+      rasterize_triangle(
+        vertices,
+        args.canvas_size().size(),
+        |position, depth| {
+          let biased_depth = (depth + depth_bias).min(1.0);
+          let Some(drawn_material) =
+            style.draw_face_cell(biased_depth, intensity)
+          else {
+            return;
+          };
+          buffer
+            .draw(drawn_material, ProjectedPoint::new(position, biased_depth));
+        },
+      );
+    }
+  }
+}
+
+/// Finds the edges of a mesh worth drawing as lines: ones where the faces on
+/// either side meet at more than `min_angle` radians, and ones that don't have
+/// exactly two faces (like the borders of an open mesh). Seams between
+/// coplanar triangles are left out.
+///
+/// Edges are only shared if their triangles share vertex indices, so
+/// vertices at the same position should be merged first.
+pub fn detect_feature_edges(
+  vertices: &[Vec3],
+  triangles: &[[usize; 3]],
+  min_angle: f32,
+) -> Vec<(usize, usize)> {
+  let mut edge_normals = HashMap::<(usize, usize), Vec<Vec3>>::new();
+  for &[a, b, c] in triangles {
+    if !indices_in_range(&[a, b, c], vertices.len()) {
+      continue;
+    }
+    let normal = (vertices[b] - vertices[a])
+      .cross(vertices[c] - vertices[a])
+      .normalize_or_zero();
+    for (from, to) in [(a, b), (b, c), (c, a)] {
+      edge_normals
+        .entry((from.min(to), from.max(to)))
+        .or_default()
+        .push(normal);
+    }
+  }
+
+  let mut edges = edge_normals
+    .into_iter()
+    .filter(|(_, normals)| match normals.as_slice() {
+      &[a, b] => a.angle_between(b) > min_angle,
+      _ => true,
+    })
+    .map(|(edge, _)| edge)
+    .collect::<Vec<_>>();
+  // keep the draw order stable, since the map's order isn't
+  edges.sort_unstable();
+  edges
+}
+
+/// Clips a view-space triangle to the visible depth range, returning the
+/// corners of what's left of it in order.
+fn clip_to_depth_range(args: &CanvasArgs, triangle: [Vec3; 3]) -> Vec<Vec3> {
+  let mut polygon = triangle.to_vec();
+
+  // clip against the near plane, then the far plane. depth is linear in view
+  // space, so the crossing points can be found by lerping.
+  for (boundary, facing) in [(0.0, 1.0), (1.0, -1.0)] {
+    let inside = |depth: f32| (depth - boundary) * facing >= 0.0;
+    let clipped = (0..polygon.len())
+      .flat_map(|i| {
+        let from = polygon[i];
+        let to = polygon[(i + 1) % polygon.len()];
+        let from_depth = args.view_depth(from);
+        let to_depth = args.view_depth(to);
+
+        let crossing = (inside(from_depth) != inside(to_depth)).then(|| {
+          from.lerp(to, (boundary - from_depth) / (to_depth - from_depth))
+        });
+        inside(from_depth)
+          .then_some(from)
+          .into_iter()
+          .chain(crossing)
+      })
+      .collect::<Vec<_>>();
+    polygon = clipped;
+  }
+
+  polygon
+}
+
+/// Scanline-rasterizes a triangle, calling `draw` with every canvas cell whose
+/// center lies within it and the depth there.
+///
+/// Cells on the left and top edges are included and ones on the right and
+/// bottom edges aren't, so triangles sharing an edge don't both draw it.
+fn rasterize_triangle(
+  vertices: [RasterVertex; 3],
+  extent: UVec2,
+  mut draw: impl FnMut(IVec2, f32),
+) {
+  let [a, b, c] = vertices.map(|v| v.position);
+  let area = (b - a).perp_dot(c - a);
+  if area.abs() <= f32::EPSILON {
+    return;
+  }
+
+  let min_y = a.y.min(b.y).min(c.y);
+  let max_y = a.y.max(b.y).max(c.y);
+  let first_row = ((min_y - 0.5).ceil() as i32).max(0);
+  let last_row = ((max_y - 0.5).ceil() as i32).min(extent.y as i32);
+
+  for y in first_row..last_row {
+    let center_y = y as f32 + 0.5;
+
+    // find where the row crosses the triangle's edges
+    let (mut left, mut right) = (f32::INFINITY, f32::NEG_INFINITY);
+    for (from, to) in [(a, b), (b, c), (c, a)] {
+      let (top, bottom) = if from.y < to.y {
+        (from, to)
+      } else {
+        (to, from)
+      };
+      if !(top.y..bottom.y).contains(&center_y) {
+        continue;
+      }
+      let x =
+        top.x + (center_y - top.y) * (bottom.x - top.x) / (bottom.y - top.y);
+      left = left.min(x);
+      right = right.max(x);
+    }
+    if left > right {
+      continue;
+    }
+
+    let first_column = ((left - 0.5).ceil() as i32).max(0);
+    let last_column = ((right - 0.5).ceil() as i32).min(extent.x as i32);
+
+    for x in first_column..last_column {
+      let center = Vec2::new(x as f32 + 0.5, center_y);
+
+      // barycentric weights, which are linear across the canvas
+      let weight_a = (c - b).perp_dot(center - b) / area;
+      let weight_b = (a - c).perp_dot(center - c) / area;
+      let weights = [weight_a, weight_b, 1.0 - weight_a - weight_b];
+
+      // interpolate through `1 / w` so depth is correct under perspective
+      let (inv_w, depth_over_w) = weights.iter().zip(&vertices).fold(
+        (0.0, 0.0),
+        |(inv_w, depth_over_w), (weight, vertex)| {
+          (
+            inv_w + weight * vertex.inv_w,
+            depth_over_w + weight * vertex.depth_over_w,
+          )
+        },
+      );
+      draw(IVec2::new(x, y), (depth_over_w / inv_w).clamp(0.0, 1.0));
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{
+    camera::Camera,
+    headless::HeadlessRenderer,
+    shapes::{LinePattern, LineVariant},
+  };
+
+  fn vertex(x: f32, y: f32) -> RasterVertex {
+    RasterVertex {
+      position:     Vec2::new(x, y),
+      inv_w:        1.0,
+      depth_over_w: 0.5,
+    }
+  }
+
+  /// How many times each cell of an `extent`-sized canvas is drawn by the
+  /// triangles.
+  fn draw_counts(triangles: &[[RasterVertex; 3]], extent: UVec2) -> Vec<u32> {
+    let mut counts = vec![0; (extent.x * extent.y) as usize];
+    for triangle in triangles {
+      rasterize_triangle(*triangle, extent, |position, _| {
+        counts[(position.y as u32 * extent.x + position.x as u32) as usize] +=
+          1;
+      });
+    }
+    counts
+  }
+
+  #[test]
+  fn shared_edges_are_drawn_once() {
+    // a square split along its diagonal, with the diagonal running through
+    // cell centers
+    let [a, b, c, d] = [
+      vertex(0.0, 0.0),
+      vertex(8.0, 0.0),
+      vertex(8.0, 8.0),
+      vertex(0.0, 8.0),
+    ];
+    let counts = draw_counts(&[[a, b, c], [a, c, d]], UVec2::new(8, 8));
+    assert!(counts.iter().all(|&count| count == 1), "{counts:?}");
+  }
+
+  #[test]
+  fn winding_doesnt_change_the_cells_drawn() {
+    let [a, b, c] = [vertex(0.5, 0.5), vertex(7.5, 2.0), vertex(3.0, 7.5)];
+    let extent = UVec2::new(8, 8);
+    assert_eq!(
+      draw_counts(&[[a, b, c]], extent),
+      draw_counts(&[[a, c, b]], extent)
+    );
+  }
+
+  #[test]
+  fn degenerate_triangles_draw_nothing() {
+    let extent = UVec2::new(8, 8);
+    let point = [vertex(2.0, 2.0); 3];
+    let line = [vertex(0.0, 0.0), vertex(4.0, 4.0), vertex(8.0, 8.0)];
+    let counts = draw_counts(&[point, line], extent);
+    assert!(counts.iter().all(|&count| count == 0));
+  }
+
+  #[test]
+  fn triangles_are_cropped_to_the_canvas() {
+    let triangle = [vertex(-4.0, -4.0), vertex(20.0, -4.0), vertex(-4.0, 20.0)];
+    let counts = draw_counts(&[triangle], UVec2::new(4, 4));
+    assert!(counts.iter().all(|&count| count == 1));
+  }
+
+  #[test]
+  fn checks_indices_are_in_range() {
+    assert!(indices_in_range(&[0, 1, 2], 3));
+    assert!(!indices_in_range(&[0, 1, 3], 3));
+    assert!(!indices_in_range(&[0], 0));
+    assert!(indices_in_range(&[], 0));
+  }
+
+  #[test]
+  fn feature_edges_skip_triangles_out_of_range() {
+    let vertices = [Vec3::ZERO, Vec3::X, Vec3::Y];
+    let edges = detect_feature_edges(&vertices, &[[0, 1, 2], [0, 2, 7]], 0.5);
+    assert_eq!(edges, vec![(0, 1), (0, 2), (1, 2)]);
+  }
+
+  #[test]
+  fn meshes_with_indices_out_of_range_still_draw() {
+    let mut renderer = HeadlessRenderer::new(
+      UVec2::new(24, 12),
+      Camera::default().with_scale(0.2),
+      Transform::from_xyz(0.0, 0.0, 10.0),
+    );
+    renderer.spawn_shape(
+      MeshArgs {
+        vertices:      vec![Vec3::ZERO, Vec3::X, Vec3::Y],
+        triangles:     vec![[0, 1, 2], [0, 1, 9]],
+        feature_edges: vec![(0, 1), (1, 9)],
+        style:         MeshStyle {
+          face_material: Some(Material::wall_face()),
+          face_shading:  None,
+          edge_style:    Some(LineStyle {
+            material:     Material::wall_edge(),
+            cap_material: None,
+            variant:      LineVariant::Thin,
+            pattern:      LinePattern::Solid,
+            hidden:       None,
+          }),
+        },
+      },
+      Transform::IDENTITY,
+    );
+
+    let frame = renderer.render_frame();
+    assert!(frame.content.iter().any(|cell| cell.symbol() != " "));
+  }
+}
@@ -1,14 +1,25 @@
 use bevy::prelude::*;
+use ratatui::style::Color;
 
 use super::{
-  CanvasArgs, DrawnShape, Material, ShapeBuffer, line::basic_8_connected,
+  CanvasArgs, DrawnShape, FaceShading, Material, ShapeBuffer,
+  line::basic_8_connected,
 };
 use crate::{
   MAX_PROJECTED_DEPTH,
-  shapes::{MaterialDrawRequest, MaterialDrawRequestType, ProjectedPoint},
+  culling::ShapeBounds,
+  shapes::{DrawnMaterial, MaterialDrawRequest, ProjectedPoint},
 };
 
+/// How far faces are pushed back, so that the edges around them win.
 const PLANE_DEPTH_BIAS: f32 = 0.05 / MAX_PROJECTED_DEPTH;
+/// How many cells' worth of a face's depth slope it's also pushed back by.
+/// Lines only know their depth to within about a cell, so without this, steep
+/// faces cover the edges along their own borders.
+const FACE_BIAS_CELLS: f32 = 1.5;
+/// The most rows a plane is drawn with, so that a plane running up to the
+/// near plane of a perspective camera can't take forever to draw.
+const MAX_PLANE_ROWS: usize = 1 << 14;
 
 pub struct PlaneArgs {
   pub xy_half_extents: Vec2,
@@ -19,6 +30,46 @@ pub struct PlaneArgs {
 #[derive(Clone)]
 pub struct PlaneStyle {
   pub material: Material,
+  /// The background color the plane is drawn with instead of its material's.
+  pub tint:     Option<Color>,
+  /// How the plane is shaded by the scene's lights, if it is. Planes are lit
+  /// on their local +Z side.
+  pub shading:  Option<FaceShading>,
+}
+
+impl PlaneStyle {
+  /// Draws a cell of a face that's lit with `intensity`, if it's shaded.
+  pub(super) fn draw_face_cell(
+    &self,
+    depth: f32,
+    intensity: Option<f32>,
+  ) -> Option<DrawnMaterial> {
+    // faces have no neighbors to give, so line materials can't be drawn
+    let mut drawn = self
+      .material
+      .draw_or_warn(MaterialDrawRequest::None, depth)?;
+    if let Some(tint) = self.tint {
+      drawn = drawn.with_tint(tint);
+    }
+    match (&self.shading, intensity) {
+      (Some(shading), Some(intensity)) => Some(shading.shade(drawn, intensity)),
+      _ => Some(drawn),
+    }
+  }
+
+  /// How brightly a face at `point` facing along `normal` is lit, both in
+  /// world space, if the style shades it.
+  pub(super) fn face_intensity(
+    &self,
+    args: &CanvasArgs,
+    point: Vec3,
+    normal: Vec3,
+  ) -> Option<f32> {
+    self
+      .shading
+      .as_ref()
+      .map(|_| args.light_intensity(point, normal))
+  }
 }
 
 const PLANE_POINTS: [Vec3; 4] = [
@@ -28,6 +79,27 @@ const PLANE_POINTS: [Vec3; 4] = [
   Vec3::new(-1.0, -1.0, 0.0),
 ];
 
+/// How far to push back a face whose plane passes through the given canvas
+/// positions and depths, so that the edges along its borders are drawn over
+/// it.
+pub(super) fn face_depth_bias(corners: [(Vec2, f32); 3]) -> f32 {
+  let [(a, a_depth), (b, b_depth), (c, c_depth)] = corners;
+  let (ab, ac) = (b - a, c - a);
+  let det = ab.perp_dot(ac);
+  // a face seen edge-on covers nothing but its own edges
+  if det.abs() <= f32::EPSILON {
+    return 1.0;
+  }
+
+  // the depth gradient across the canvas
+  let (ab_depth, ac_depth) = (b_depth - a_depth, c_depth - a_depth);
+  let gradient = Vec2::new(
+    ab_depth * ac.y - ac_depth * ab.y,
+    ac_depth * ab.x - ab_depth * ac.x,
+  ) / det;
+  PLANE_DEPTH_BIAS + gradient.abs().element_sum() * FACE_BIAS_CELLS
+}
+
 impl DrawnShape for PlaneArgs {
   fn draw(
     &self,
@@ -41,89 +113,94 @@ impl DrawnShape for PlaneArgs {
       style,
     } = self;
 
-    let scaled_points =
-      PLANE_POINTS.iter().map(|p| p * xy_half_extents.extend(1.0));
-
-    let transformed_points =
-      scaled_points.map(|p| transform.transform_point(p));
-
-    let canvas_points = transformed_points
-      .map(|p| args.world_to_canvas_coords(p))
-      .collect::<Vec<_>>();
-
-    // the left and right vertical lines (as if it had no transform)
-    let mut left_vert_line =
-      basic_8_connected(canvas_points[0], canvas_points[3]);
-    let mut right_vert_line =
-      basic_8_connected(canvas_points[1], canvas_points[2]);
-
-    if *exclude_borders {
-      // skips one on both sides by skip, reverse, skip, and then reverse
-      left_vert_line = left_vert_line
-        .into_iter()
-        .skip(1)
-        .rev()
-        .skip(1)
-        .rev()
-        .collect();
-      right_vert_line = right_vert_line
-        .into_iter()
-        .skip(1)
-        .rev()
-        .skip(1)
-        .rev()
-        .collect();
-    }
+    let view_points = PLANE_POINTS.map(|p| {
+      args.world_to_view(
+        transform.transform_point(p * xy_half_extents.extend(1.0)),
+      )
+    });
 
-    // make sure the left and right sides are the same length
-    match left_vert_line.len() as i32 - right_vert_line.len() as i32 {
-      // left is less than right
-      i @ ..0 => {
-        // copy the last point of the left line to make the list long enough
-        if left_vert_line.is_empty() {
-          return;
-        }
-        let last_point = *left_vert_line.last().unwrap();
-        left_vert_line.extend(std::iter::repeat(last_point).take(-i as usize));
-      }
-      // equal
-      0 => (),
-      // left is greater than right
-      i @ 1.. => {
-        // copy the last point of the right line to make the list long enough
-        if right_vert_line.is_empty() {
-          return;
-        }
-        let last_point = *right_vert_line.last().unwrap();
-        right_vert_line.extend(std::iter::repeat(last_point).take(i as usize));
-      }
+    // the left and right vertical edges (as if it had no transform)
+    let left_edge = (view_points[0], view_points[3]);
+    let right_edge = (view_points[1], view_points[2]);
+
+    // only walk the part of the plane that's within the depth range
+    let left_range = args.view_depth_range(left_edge.0, left_edge.1);
+    let right_range = args.view_depth_range(right_edge.0, right_edge.1);
+    let (min_v, max_v) = match (left_range, right_range) {
+      (None, None) => return,
+      (Some(range), None) | (None, Some(range)) => range,
+      (Some(left), Some(right)) => (left.0.min(right.0), left.1.max(right.1)),
+    };
+
+    // step down the edges at most one cell at a time. the edges move fastest
+    // on the canvas at their ends, so checking there is enough.
+    let max_speed = [(left_edge, left_range), (right_edge, right_range)]
+      .into_iter()
+      .filter_map(|(edge, range)| Some((edge, range?)))
+      .flat_map(|((from, to), (t0, t1))| {
+        [
+          args.canvas_speed(from, to, t0),
+          args.canvas_speed(from, to, t1),
+        ]
+      })
+      .fold(0.0, f32::max);
+    let row_count =
+      ((max_speed * (max_v - min_v)).ceil() as usize).clamp(1, MAX_PLANE_ROWS);
+
+    let depth_bias = if view_points.iter().all(|p| args.view_depth(*p) >= 0.0) {
+      face_depth_bias(
+        [view_points[0], view_points[1], view_points[3]]
+          .map(|p| (args.view_to_canvas_position(p), args.view_depth(p))),
+      )
+    } else {
+      // the corners behind the camera don't project sensibly
+      PLANE_DEPTH_BIAS
     };
-    debug_assert_eq!(left_vert_line.len(), right_vert_line.len());
 
+    let intensity = style.face_intensity(
+      args,
+      transform.translation,
+      transform.rotation * Vec3::Z,
+    );
     let mut materials_to_draw = Vec::new();
 
     // for each pair of points on the right and left, draw a line across
-    let left_to_right = left_vert_line.into_iter().zip(right_vert_line);
-    for (left_point, right_point) in left_to_right {
-      let mut row = basic_8_connected(left_point, right_point);
+    for row_index in 0..=row_count {
+      let v = min_v + (max_v - min_v) * row_index as f32 / row_count as f32;
+
+      if *exclude_borders
+        && ((row_index == 0 && min_v == 0.0)
+          || (row_index == row_count && max_v == 1.0))
+      {
+        continue;
+      }
+
+      let Some(segment) = args.clip_view_segment(
+        left_edge.0.lerp(left_edge.1, v),
+        right_edge.0.lerp(right_edge.1, v),
+      ) else {
+        continue;
+      };
+      let mut row = basic_8_connected(segment.from, segment.to);
 
       if *exclude_borders {
-        // skips one on both sides by skip, reverse, skip, and then reverse
-        row = row.into_iter().skip(1).rev().skip(1).rev().collect();
+        // skip the real ends of the row, but not ones made by clipping
+        if !segment.to_clipped {
+          row.pop();
+        }
+        if !segment.from_clipped && !row.is_empty() {
+          row.remove(0);
+        }
       }
 
       for point in row {
-        let mat_request_type = style.material.draw_request_type();
-        let request = match mat_request_type {
-          MaterialDrawRequestType::None => MaterialDrawRequest::None,
-          MaterialDrawRequestType::Neighbors => {
-            panic!("cannot draw sequenced material in plane (yet)")
-          }
+        let biased_depth = (point.depth() + depth_bias).min(1.0);
+        let Some(drawn_material) =
+          style.draw_face_cell(biased_depth, intensity)
+        else {
+          continue;
         };
 
-        let biased_depth = point.depth() + PLANE_DEPTH_BIAS;
-        let drawn_material = style.material.draw(request, biased_depth);
-
         materials_to_draw.push((
           drawn_material,
           ProjectedPoint::new(point.pos(), biased_depth),
@@ -148,6 +225,13 @@ impl DrawnShape for PlaneArgs {
       buffer.draw(point.0, point.1);
     }
   }
+
+  fn bounds(&self) -> Option<ShapeBounds> {
+    Some(ShapeBounds::new(
+      Vec3::ZERO,
+      self.xy_half_extents.extend(0.0),
+    ))
+  }
 }
 
 // fn simple_line(
@@ -1,9 +1,11 @@
 use bevy::prelude::*;
 
 use super::{
-  DrawnShape, Material, MaterialDrawRequest, MaterialDrawRequestType,
+  ClippedSegment, DrawnShape, HiddenLineStyle, LinePattern, LineVariant,
+  Material, MaterialDrawRequest, MaterialDrawRequestType, ProjectedPoint,
   basic_8_connected, thin_neighbor::Neighbor,
 };
+use crate::culling::ShapeBounds;
 
 pub struct PolylineArgs {
   pub points: Vec<Vec3>,
@@ -14,6 +16,10 @@ pub struct PolylineArgs {
 pub struct PolylineStyle {
   pub material:   Material,
   pub loop_style: PolylineLoopStyle,
+  pub variant:    LineVariant,
+  pub pattern:    LinePattern,
+  /// How the hidden parts are drawn. See [`HiddenLineStyle`].
+  pub hidden:     Option<HiddenLineStyle>,
 }
 
 #[derive(Clone)]
@@ -27,6 +33,30 @@ pub enum PolylineLoopStyle {
   },
 }
 
+/// The rasterized points of one clipped segment of a polyline.
+struct PointSet {
+  points:         Vec<ProjectedPoint>,
+  /// The index of the segment in the polyline.
+  index:          usize,
+  segment:        ClippedSegment,
+  /// How far along the polyline the first point is, in cells.
+  first_distance: u32,
+}
+
+/// A point of the polyline, ready to be drawn.
+struct PolylinePoint {
+  point:    ProjectedPoint,
+  /// Whether this is the (unclipped) end of a segment.
+  is_cap:   bool,
+  /// Whether this is the (unclipped) first or last point of an open
+  /// polyline.
+  is_end:   bool,
+  /// Whether clipping cut the polyline after this point.
+  is_cut:   bool,
+  /// How far along the polyline this point is, in cells.
+  distance: u32,
+}
+
 impl DrawnShape for PolylineArgs {
   fn draw(
     &self,
@@ -45,68 +75,147 @@ impl DrawnShape for PolylineArgs {
 
     let transformed_endpoints = world_endpoints
       .iter()
-      .map(|p| transform.transform_point(*p));
-
-    let canvas_endpoints = transformed_endpoints
-      .map(|p| args.world_to_canvas_coords(p))
+      .map(|p| transform.transform_point(*p))
       .collect::<Vec<_>>();
 
-    let endpoint_pairs =
-      canvas_endpoints.iter().zip(canvas_endpoints.iter().skip(1));
+    let endpoint_pairs = transformed_endpoints
+      .iter()
+      .zip(transformed_endpoints.iter().skip(1));
 
     // tack on the last point pair if the polyline is closed
     let endpoint_pairs: Box<dyn Iterator<Item = _>> =
       if matches!(style.loop_style, PolylineLoopStyle::Closed { .. }) {
         Box::new(endpoint_pairs.chain(std::iter::once((
-          canvas_endpoints.last().unwrap(),
-          canvas_endpoints.first().unwrap(),
+          transformed_endpoints.last().unwrap(),
+          transformed_endpoints.first().unwrap(),
         ))))
       } else {
         Box::new(endpoint_pairs)
       };
+    let endpoint_pairs = endpoint_pairs.collect::<Vec<_>>();
+    let segment_count = endpoint_pairs.len();
 
+    // how far along the whole polyline each segment starts, in cells, so the
+    // pattern runs on from one segment into the next
+    let segment_distances = endpoint_pairs
+      .iter()
+      .scan(0u32, |distance, (from, to)| {
+        let start = *distance;
+        let canvas_delta = args.world_to_canvas_position(**to)
+          - args.world_to_canvas_position(**from);
+        let length = canvas_delta.abs().max_element().round();
+        // segments running up to the camera can be billions of cells long,
+        // so saturate rather than overflow
+        let length = if length.is_finite() { length as u32 } else { 0 };
+        *distance = distance.saturating_add(length);
+        Some(start)
+      })
+      .collect::<Vec<_>>();
+
+    // clip each segment, dropping the ones that aren't visible at all
     let mut point_sets = endpoint_pairs
-      .map(|(from, to)| basic_8_connected(*from, *to))
+      .into_iter()
+      .enumerate()
+      .filter_map(|(index, (from, to))| {
+        let segment = args.clip_segment(*from, *to)?;
+        Some(PointSet {
+          points: basic_8_connected(segment.from, segment.to),
+          index,
+          segment,
+          first_distance: segment_distances[index]
+            .saturating_add(segment.skipped_cells),
+        })
+      })
       .collect::<Vec<_>>();
 
     // if a set contains no points, drop it
-    point_sets.retain(|points| !points.is_empty());
+    point_sets.retain(|set| !set.points.is_empty());
 
     // check at the end of each set to see if the next set has the same point.
     // if so, keep the point with the lower depth (closer)
     for i in 0..point_sets.len() {
       let next_set = (i + 1) % point_sets.len();
-      let at_end_of_set = point_sets[i].last();
-      let at_start_of_next_set = point_sets[next_set].first();
+      let at_end_of_set = point_sets[i].points.last();
+      let at_start_of_next_set = point_sets[next_set].points.first();
 
       match (at_end_of_set, at_start_of_next_set) {
         (Some(end), Some(start)) if end.pos() == start.pos() => {
           if end.depth() < start.depth() {
-            point_sets[i].pop();
+            point_sets[i].points.pop();
           } else {
-            point_sets[next_set].remove(0);
+            point_sets[next_set].points.remove(0);
+            point_sets[next_set].first_distance =
+              point_sets[next_set].first_distance.saturating_add(1);
           }
         }
         _ => {}
       }
     }
 
-    // the list of points, with a boolean that's true at the end of each set
+    // whether the end of each set runs straight into the start of the next
+    // one. if it doesn't, clipping cut the polyline in between them.
+    let connected_to_next = (0..point_sets.len())
+      .map(|i| {
+        let set = &point_sets[i];
+        let next_set = &point_sets[(i + 1) % point_sets.len()];
+        next_set.index == (set.index + 1) % segment_count
+          && !set.segment.to_clipped
+          && !next_set.segment.from_clipped
+      })
+      .collect::<Vec<_>>();
+
+    let is_open = matches!(style.loop_style, PolylineLoopStyle::Open { .. });
     let points = point_sets
       .into_iter()
-      .flat_map(|set| {
-        let len = set.len();
-        set
-          .into_iter()
-          .enumerate()
-          .map(move |(i, point)| (point, i == len - 1))
+      .zip(connected_to_next)
+      .flat_map(|(set, connected_to_next)| {
+        let len = set.points.len();
+        let end_clipped = set.segment.to_clipped;
+        let is_first = set.index == 0 && !set.segment.from_clipped;
+        let is_last = set.index == segment_count - 1 && !end_clipped;
+        set.points.into_iter().enumerate().map(move |(i, point)| {
+          let at_end = i == len - 1;
+          PolylinePoint {
+            point,
+            is_cap: at_end && !end_clipped,
+            is_end: is_open && ((i == 0 && is_first) || (at_end && is_last)),
+            is_cut: at_end && !connected_to_next,
+            distance: set.first_distance.saturating_add(i as u32),
+          }
+        })
       })
       .collect::<Vec<_>>();
 
     const NEIGHBOR_STEP: usize = 1;
 
-    for (i, (point, is_cap)) in points.iter().enumerate() {
-      let (next_point_index, prev_point_index) = match style.loop_style {
+    for (i, polyline_point) in points.iter().enumerate() {
+      let PolylinePoint {
+        point,
+        is_cap,
+        is_end,
+        is_cut,
+        distance,
+      } = polyline_point;
+
+      // leave gaps in the pattern undrawn, unless there's a cap here
+      let has_cap_material = match &style.loop_style {
+        PolylineLoopStyle::Open {
+          point_cap_material,
+          end_cap_material,
+        } => {
+          (*is_end && end_cap_material.is_some())
+            || (*is_cap && point_cap_material.is_some())
+        }
+        PolylineLoopStyle::Closed { point_cap_material } => {
+          *is_cap && point_cap_material.is_some()
+        }
+      };
+      if !has_cap_material && !style.pattern.is_drawn(*distance) {
+        continue;
+      }
+
+      let (mut next_point_index, mut prev_point_index) = match style.loop_style
+      {
         PolylineLoopStyle::Open { .. } => {
           let next_point_index = (i + NEIGHBOR_STEP).min(points.len() - 1);
           let prev_point_index = i.saturating_sub(NEIGHBOR_STEP);
@@ -120,10 +229,21 @@ impl DrawnShape for PolylineArgs {
         }
       };
 
-      let next_point = points[next_point_index].0;
-      let prev_point = points[prev_point_index].0;
+      // don't look for neighbors across a cut
+      if *is_cut {
+        next_point_index = i;
+      }
+      if points[prev_point_index].is_cut {
+        prev_point_index = i;
+      }
+
+      let next_point = points[next_point_index].point;
+      let prev_point = points[prev_point_index].point;
       let next_point_offset = next_point.pos() - point.pos();
       let prev_point_offset = prev_point.pos() - point.pos();
+      let (prev_point_offset, next_point_offset) = style
+        .variant
+        .fill_missing_offset(prev_point_offset, next_point_offset);
       let prev_neighbor =
         Neighbor::find(prev_point_offset, args.character_aspect_ratio());
       let next_neighbor =
@@ -135,11 +255,14 @@ impl DrawnShape for PolylineArgs {
           point_cap_material,
           end_cap_material,
         } => {
-          if *is_cap {
-            end_cap_material.unwrap_or(style.material.clone())
+          let cap_material = if *is_end {
+            end_cap_material
+          } else if *is_cap {
+            point_cap_material
           } else {
-            point_cap_material.unwrap_or(style.material.clone())
-          }
+            None
+          };
+          cap_material.unwrap_or(style.material.clone())
         }
         PolylineLoopStyle::Closed { point_cap_material } => {
           if *is_cap {
@@ -150,22 +273,85 @@ impl DrawnShape for PolylineArgs {
         }
       };
 
-      // figure out what info we need
-      let mat_request_type = material.draw_request_type();
-
-      // fill in the info
-      let request = match mat_request_type {
+      // figure out what info each material needs, and fill it in
+      let request_for = |material: &Material| match material.draw_request_type()
+      {
         MaterialDrawRequestType::None => MaterialDrawRequest::None,
         MaterialDrawRequestType::Neighbors => MaterialDrawRequest::Neighbors {
-          prev: prev_neighbor,
-          next: next_neighbor,
+          prev:    prev_neighbor,
+          next:    next_neighbor,
+          variant: style.variant,
         },
       };
 
-      // determine the character
-      let drawn_material = material.draw(request, point.depth());
+      // determine the character, and how it's drawn if it's hidden
+      let Some(mut drawn_material) =
+        material.draw_or_warn(request_for(&material), point.depth())
+      else {
+        continue;
+      };
+      if let Some(hidden) = style
+        .hidden
+        .as_ref()
+        .filter(|h| h.pattern.is_drawn(*distance))
+        .and_then(|h| {
+          h.material
+            .draw_or_warn(request_for(&h.material), point.depth())
+        })
+      {
+        drawn_material = drawn_material.with_hidden(hidden);
+      }
 
       buffer.draw(drawn_material, *point);
     }
   }
+
+  fn bounds(&self) -> Option<ShapeBounds> {
+    Some(ShapeBounds::from_points(self.points.iter().copied()))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{
+    camera::{Camera, CameraProjection},
+    headless::HeadlessRenderer,
+  };
+
+  #[test]
+  fn pattern_distance_saturates_near_the_camera() {
+    let mut renderer = HeadlessRenderer::new(
+      UVec2::new(48, 20),
+      Camera::default().with_projection(CameraProjection::Perspective {
+        fov:  1.0,
+        near: 0.1,
+      }),
+      Transform::IDENTITY,
+    );
+    // the middle vertex sits just in front of the camera and far off to the
+    // side, so the segments either side of it are billions of cells long
+    renderer.spawn_shape(
+      PolylineArgs {
+        points: vec![
+          Vec3::new(-1.0, 0.0, -5.0),
+          Vec3::new(1.0e9, 0.0, -0.2),
+          Vec3::new(1.0, 0.0, -5.0),
+          Vec3::new(1.0, 1.0, -5.0),
+        ],
+        style:  PolylineStyle {
+          material:   Material::wall_edge(),
+          loop_style: PolylineLoopStyle::Closed {
+            point_cap_material: None,
+          },
+          variant:    LineVariant::Thin,
+          pattern:    LinePattern::Dashed { dash: 2, gap: 1 },
+          hidden:     None,
+        },
+      },
+      Transform::IDENTITY,
+    );
+
+    renderer.render_frame();
+  }
 }
@@ -0,0 +1,215 @@
+//! Shared pieces of the round shapes: spheres, cylinders and cones.
+//!
+//! Their outlines are worked out in the shape's local space from the camera
+//! center as a homogeneous point `(e, w)`, which is `w = 1` for perspective
+//! cameras and `w = 0` for orthographic ones. A surface point `p` with outward
+//! normal `n` faces the camera when `n · (e - p * w) > 0`, and the silhouette
+//! is where that's 0.
+
+use std::f32::consts::{PI, TAU};
+
+use bevy::prelude::*;
+
+use super::{
+  CanvasArgs, DrawnShape, LinePattern, LineVariant, Material, PolylineArgs,
+  PolylineLoopStyle, PolylineStyle, ShapeBuffer,
+};
+
+/// How long each segment of a curve is on the canvas, roughly, in canvas
+/// points.
+pub(super) const CANVAS_POINTS_PER_SEGMENT: f32 = 5.0;
+/// The fewest segments a full circle is split into.
+const MIN_CIRCLE_SEGMENTS: usize = 8;
+/// The most segments a full circle is split into, for circles passing close
+/// to the camera.
+const MAX_CIRCLE_SEGMENTS: usize = 512;
+
+#[derive(Clone)]
+pub struct RoundStyle {
+  /// The material of the silhouette and rims.
+  pub material:       Material,
+  pub pattern:        LinePattern,
+  /// The material of the latitude/longitude or ring lines, if they're drawn.
+  pub guide_material: Option<Material>,
+  pub guide_pattern:  LinePattern,
+}
+
+/// A circle in a shape's local space.
+#[derive(Clone, Copy)]
+pub(super) struct LocalCircle {
+  pub center: Vec3,
+  /// The offset from the center to the point at angle 0.
+  pub u:      Vec3,
+  /// The offset from the center to the point at a quarter turn.
+  pub v:      Vec3,
+}
+
+impl LocalCircle {
+  /// A circle around the Y axis, at height `y`.
+  pub fn around_y(y: f32, radius: f32) -> Self {
+    LocalCircle {
+      center: Vec3::new(0.0, y, 0.0),
+      u:      Vec3::X * radius,
+      v:      Vec3::Z * radius,
+    }
+  }
+
+  /// A circle around the origin, in the local XY plane rotated by `plane`.
+  pub fn in_plane(plane: Quat, radius: f32) -> Self {
+    LocalCircle {
+      center: Vec3::ZERO,
+      u:      plane * Vec3::X * radius,
+      v:      plane * Vec3::Y * radius,
+    }
+  }
+
+  pub fn point_at(&self, angle: f32) -> Vec3 {
+    self.center + self.u * angle.cos() + self.v * angle.sin()
+  }
+
+  /// The points splitting the circle into `n_segments` equal segments,
+  /// starting at angle 0.
+  pub fn points(&self, n_segments: usize) -> impl Iterator<Item = Vec3> + '_ {
+    (0..n_segments)
+      .map(move |i| self.point_at(i as f32 * TAU / n_segments as f32))
+  }
+
+  /// The half-extents of the box the circle fits in.
+  pub fn half_extents(&self) -> Vec3 {
+    (self.u * self.u + self.v * self.v).map(f32::sqrt)
+  }
+
+  /// How many segments the circle needs to look round, from how big it is on
+  /// the canvas.
+  pub fn segment_count(
+    &self,
+    args: &CanvasArgs,
+    transform: &Transform,
+  ) -> usize {
+    let canvas = |point: Vec3| {
+      args.world_to_canvas_position(transform.transform_point(point))
+    };
+    let center = canvas(self.center);
+
+    // the projected offsets to `u` and `v` are conjugate radii of the
+    // projected ellipse, and its real radii are their singular values
+    let u = canvas(self.center + self.u) - center;
+    let v = canvas(self.center + self.v) - center;
+    let sum_of_squares = u.length_squared() + v.length_squared();
+    let product = u.perp_dot(v).abs();
+    let spread = (sum_of_squares * sum_of_squares - 4.0 * product * product)
+      .max(0.0)
+      .sqrt();
+    let major = ((sum_of_squares + spread) / 2.0).sqrt();
+    let minor = ((sum_of_squares - spread).max(0.0) / 2.0).sqrt();
+
+    // Ramanujan's approximation of the ellipse's perimeter
+    let perimeter = PI
+      * (3.0 * (major + minor)
+        - ((3.0 * major + minor) * (major + 3.0 * minor)).sqrt());
+    if !perimeter.is_finite() {
+      return MAX_CIRCLE_SEGMENTS;
+    }
+
+    ((perimeter / CANVAS_POINTS_PER_SEGMENT).ceil() as usize)
+      .clamp(MIN_CIRCLE_SEGMENTS, MAX_CIRCLE_SEGMENTS)
+  }
+}
+
+/// Draws the parts of `circle` where `facing` is positive, as arcs. `facing`
+/// should change sign smoothly, since the arcs' ends are found by
+/// interpolating it between samples.
+pub(super) fn draw_circle_arcs(
+  circle: LocalCircle,
+  facing: impl Fn(Vec3) -> f32,
+  material: &Material,
+  pattern: LinePattern,
+  buffer: &mut ShapeBuffer,
+  args: &CanvasArgs,
+  transform: &Transform,
+) {
+  let n_segments = circle.segment_count(args, transform);
+  let points = circle.points(n_segments).collect::<Vec<_>>();
+  let facing_values = points.iter().map(|p| facing(*p)).collect::<Vec<_>>();
+
+  let Some(first_hidden) = facing_values.iter().position(|f| *f <= 0.0) else {
+    draw_polyline(points, true, material, pattern, buffer, args, transform);
+    return;
+  };
+
+  // walk once around the circle from a hidden sample, so every arc is whole
+  let mut arc = Vec::new();
+  for step in 1..=n_segments {
+    let i = (first_hidden + step) % n_segments;
+    let previous = (i + n_segments - 1) % n_segments;
+    let (from, to) = (facing_values[previous], facing_values[i]);
+
+    if (from > 0.0) != (to > 0.0) {
+      // the arc starts or ends between the samples
+      let t = from / (from - to);
+      let crossing = (first_hidden + step - 1) as f32 + t;
+      arc.push(circle.point_at(crossing * TAU / n_segments as f32));
+    }
+    if to > 0.0 {
+      arc.push(points[i]);
+    } else if !arc.is_empty() {
+      let points = std::mem::take(&mut arc);
+      draw_polyline(points, false, material, pattern, buffer, args, transform);
+    }
+  }
+}
+
+/// Draws a thin line through local-space `points`, closing it into a loop if
+/// `closed`.
+pub(super) fn draw_polyline(
+  points: Vec<Vec3>,
+  closed: bool,
+  material: &Material,
+  pattern: LinePattern,
+  buffer: &mut ShapeBuffer,
+  args: &CanvasArgs,
+  transform: &Transform,
+) {
+  if points.len() < 2 {
+    return;
+  }
+
+  let loop_style = if closed {
+    PolylineLoopStyle::Closed {
+      point_cap_material: None,
+    }
+  } else {
+    PolylineLoopStyle::Open {
+      point_cap_material: None,
+      end_cap_material:   None,
+    }
+  };
+  PolylineArgs {
+    points,
+    style: PolylineStyle {
+      material: material.clone(),
+      loop_style,
+      variant: LineVariant::Thin,
+      pattern,
+      hidden: None,
+    },
+  }
+  .draw(buffer, args, transform);
+}
+
+/// The angles around the Y axis where a surface of revolution's silhouette
+/// lines run, given that the silhouette is where
+/// `cos(angle) * e.x + sin(angle) * e.z = k`.
+///
+/// Returns `None` when there's no silhouette, like when looking straight
+/// down the axis.
+pub(super) fn silhouette_angles(eye: Vec4, k: f32) -> Option<[f32; 2]> {
+  let length = eye.xz().length();
+  if length <= f32::EPSILON || k.abs() >= length {
+    return None;
+  }
+
+  let direction = eye.z.atan2(eye.x);
+  let offset = (k / length).acos();
+  Some([direction - offset, direction + offset])
+}
@@ -0,0 +1,76 @@
+use ratatui::style::Color;
+use smol_str::SmolStr;
+
+use super::DrawnMaterial;
+use crate::color_depth::blend_color;
+
+/// Symbols from unlit to fully lit, getting denser as they get brighter.
+pub const DEFAULT_SYMBOL_RAMP: &str = " .:-=+*#%@";
+
+/// How a face is shaded by the light falling on it. The light is worked out
+/// once per face, from its normal.
+#[derive(Clone, Debug)]
+pub struct FaceShading {
+  /// Symbols from unlit to fully lit, one per character, like
+  /// [`DEFAULT_SYMBOL_RAMP`]. Without them, faces keep their material's
+  /// symbol.
+  pub symbols: Option<SmolStr>,
+  /// Colors from unlit to fully lit, blended between. They color the symbols
+  /// if there are any, and the face's background otherwise.
+  pub colors:  Option<Vec<Color>>,
+}
+
+impl Default for FaceShading {
+  fn default() -> Self {
+    FaceShading {
+      symbols: Some(DEFAULT_SYMBOL_RAMP.into()),
+      colors:  None,
+    }
+  }
+}
+
+impl FaceShading {
+  /// The symbol for a light intensity from 0 to 1, if there's a symbol ramp.
+  pub fn symbol_at(&self, intensity: f32) -> Option<&str> {
+    let symbols = self.symbols.as_deref()?;
+    let count = symbols.chars().count();
+    let step = ramp_position(intensity, count)?.round() as usize;
+    symbols
+      .char_indices()
+      .nth(step)
+      .map(|(i, c)| &symbols[i..i + c.len_utf8()])
+  }
+
+  /// The color for a light intensity from 0 to 1, if there's a color ramp.
+  pub fn color_at(&self, intensity: f32) -> Option<Color> {
+    let colors = self.colors.as_deref()?;
+    let position = ramp_position(intensity, colors.len())?;
+    let from = position.floor() as usize;
+    let to = (from + 1).min(colors.len() - 1);
+    Some(blend_color(colors[from], colors[to], position.fract()))
+  }
+
+  /// Shades a cell of a face lit with `intensity`.
+  pub(super) fn shade(
+    &self,
+    drawn: DrawnMaterial,
+    intensity: f32,
+  ) -> DrawnMaterial {
+    let drawn = match self.symbol_at(intensity) {
+      Some(symbol) => drawn.with_symbol(symbol.into()),
+      None => drawn,
+    };
+    match (self.color_at(intensity), &self.symbols) {
+      (Some(color), Some(_)) => drawn.with_stroke(color),
+      (Some(color), None) => drawn.with_tint(color),
+      (None, _) => drawn,
+    }
+  }
+}
+
+/// Where `intensity` falls along a ramp of `count` steps, from 0 to
+/// `count - 1`, or `None` if the ramp is empty.
+fn ramp_position(intensity: f32, count: usize) -> Option<f32> {
+  let last = count.checked_sub(1)?;
+  Some(intensity.clamp(0.0, 1.0) * last as f32)
+}
@@ -1,15 +1,23 @@
-use std::{cmp::Ordering, collections::HashMap};
+use std::cmp::Ordering;
 
 use bevy::prelude::*;
-use ratatui::{buffer::Buffer, layout::Rect};
+use ratatui::{
+  buffer::{Buffer, Cell},
+  layout::Position,
+  style::Color,
+};
 
 use super::{DrawnMaterial, ProjectedPoint};
-use crate::DEFAULT_CELL;
+use crate::{
+  camera::CanvasResolution,
+  depth_cue::{DepthCue, DepthCueFrame},
+  theme::Theme,
+};
 
 /// A single cell which has been drawn by a shape.
 struct DrawnCell {
   mat:        DrawnMaterial,
-  position:   UVec2,
+  position:   IVec2,
   proj_depth: f32,
 }
 
@@ -39,6 +47,10 @@ impl PartialEq for UnpositionedDrawnCell {
 
 impl Eq for UnpositionedDrawnCell {}
 
+impl Layered for UnpositionedDrawnCell {
+  fn is_occluder(&self) -> bool { self.mat.is_occluder() }
+}
+
 #[derive(Default)]
 pub struct ShapeBuffer {
   /// Where the data sits.
@@ -60,24 +72,34 @@ impl ShapeBuffer {
     self.extent = Some(extent);
   }
 
+  /// Removes every drawn cell, keeping the allocation.
+  pub fn clear(&mut self) { self.buffer.clear(); }
+
   /// Returns the number of cells in the buffer.
   #[allow(clippy::len_without_is_empty)]
   pub fn len(&self) -> usize { self.buffer.len() }
 
+  /// Iterates over the drawn cells, with their positions and depths.
+  pub fn cells(&self) -> impl Iterator<Item = (IVec2, f32, &DrawnMaterial)> {
+    self
+      .buffer
+      .iter()
+      .map(|cell| (cell.position, cell.proj_depth, &cell.mat))
+  }
+
   /// Draws a cell.
+  ///
+  /// Canvas coordinates are signed, so shapes can be drawn partially off the
+  /// canvas; the cells outside of it are thrown away here if we have the
+  /// extent, and when compositing otherwise.
   pub fn draw(&mut self, mat: DrawnMaterial, point: ProjectedPoint) {
-    // for now, just throw it away if it's negative
-    if point.pos().x < 0 || point.pos().y < 0 {
-      return;
-    }
-
-    let position = UVec2::new(point.pos().x as _, point.pos().y as _);
+    let position = point.pos();
 
-    // if we have the extent, throw it away if out of bounds
-    if let Some(extent) = self.extent {
-      if position.x >= extent.x || position.y >= extent.y {
-        return;
-      }
+    if self
+      .extent
+      .is_some_and(|extent| canvas_index(position, extent).is_none())
+    {
+      return;
     }
 
     self.buffer.push(DrawnCell {
@@ -86,87 +108,325 @@ impl ShapeBuffer {
       proj_depth: point.depth(),
     })
   }
+}
 
-  /// Merges multiple [`ShapeBuffer`]s.
-  pub fn merge<'a>(buffers: impl IntoIterator<Item = &'a mut Self>) -> Self {
-    let mut buffers = buffers.into_iter().collect::<Vec<_>>();
-
-    match buffers.len() {
-      0 => Self::new(),
-      1 => ShapeBuffer {
-        buffer: std::mem::take(&mut buffers[0].buffer),
-        extent: None,
-      },
-      _ => {
-        let capacity = buffers.iter().map(|b| b.buffer.len()).sum();
-
-        let mut buffer = Self {
-          buffer: Vec::with_capacity(capacity),
-          extent: None,
-        };
-
-        for ShapeBuffer { buffer: other, .. } in buffers {
-          buffer.buffer.append(other);
-        }
+/// A dense, canvas-sized grid that keeps the front two cells drawn at each
+/// position.
+///
+/// The second cell is the nearest one behind the front that isn't an
+/// occluder, if there is one, so that lines hidden behind several faces can
+/// still be drawn as hidden.
+///
+/// This is meant to be reused across frames, so that compositing doesn't
+/// allocate once it has grown to the size of the largest canvas.
+///
+/// Shape buffers are composited one at a time rather than merged in parallel.
+/// Each thread would need a canvas-sized grid of its own, which would then
+/// have to be composited again, and a single pass is already cheap next to
+/// drawing the shapes (see the `compositor_bench` example).
+#[derive(Default)]
+pub struct DepthBuffer {
+  /// The size of the canvas, in canvas points.
+  extent:     UVec2,
+  /// How the canvas points map onto terminal cells.
+  resolution: CanvasResolution,
+  /// The front two cells at each position, in row-major order.
+  cells:      Vec<Zot<UnpositionedDrawnCell>>,
+}
 
-        buffer
-      }
-    }
+/// The braille dot bit for each canvas point within a cell, indexed by
+/// `[y][x]`.
+const BRAILLE_DOT_BITS: [[u8; 2]; 4] =
+  [[0x01, 0x08], [0x02, 0x10], [0x04, 0x20], [0x40, 0x80]];
+
+impl DepthBuffer {
+  /// Clears the depth buffer and resizes it to `extent` canvas points, laid
+  /// out with `resolution`.
+  pub fn reset(&mut self, extent: UVec2, resolution: CanvasResolution) {
+    self.extent = extent;
+    self.resolution = resolution;
+    self.cells.clear();
+    self
+      .cells
+      .resize_with((extent.x * extent.y) as usize, Zot::default);
   }
 
-  /// Removes every cell deeper than the first two cells in a given position.
-  pub fn truncate(self) -> TruncatedShapeBuffer {
-    let mut map: HashMap<UVec2, Zot<UnpositionedDrawnCell>> = HashMap::new();
-
-    for cell in self.buffer.into_iter() {
+  /// Adds every cell in `buffer`, keeping the front two cells at each
+  /// position.
+  pub fn composite(&mut self, buffer: &ShapeBuffer) {
+    for cell in buffer.buffer.iter() {
       let DrawnCell {
         mat,
         position,
         proj_depth,
       } = cell;
-      let cell = UnpositionedDrawnCell { mat, proj_depth };
+      let proj_depth = *proj_depth;
 
       if !(0.0..=1.0).contains(&proj_depth) {
         continue;
       }
+      let Some(index) = canvas_index(*position, self.extent) else {
+        continue;
+      };
 
-      map.entry(position).or_default().add(cell);
+      self.cells[index].add(UnpositionedDrawnCell {
+        mat: mat.clone(),
+        proj_depth,
+      });
     }
-
-    TruncatedShapeBuffer { map }
   }
-}
 
-pub struct TruncatedShapeBuffer {
-  map: HashMap<UVec2, Zot<UnpositionedDrawnCell>>,
-}
+  /// Renders the composited cells into `buffer`, faded by `depth_cue`.
+  /// Canvas coordinates are relative to the origin of the buffer's area, and
+  /// positions with no cells are left untouched.
+  pub fn render(
+    &self,
+    buffer: &mut Buffer,
+    theme: &Theme,
+    depth_cue: &DepthCue,
+  ) {
+    if self.extent.x == 0 {
+      return;
+    }
 
-impl TruncatedShapeBuffer {
-  pub fn render(self, area: Rect) -> Buffer {
-    let mut buffer = Buffer::filled(area, DEFAULT_CELL);
+    // with nothing to measure depths against, nothing is faded
+    let range = self.front_depth_range().unwrap_or_default();
+    let cue = DepthCueFrame::new(depth_cue, range, theme);
+    let renderer = CellRenderer { theme, cue };
 
-    for (pos, zot) in self.map.into_iter() {
-      if !area.contains(ratatui::layout::Position {
-        x: pos.x as _,
-        y: pos.y as _,
-      }) {
+    match self.resolution {
+      CanvasResolution::Cell => self.render_cells(buffer, &renderer),
+      CanvasResolution::Braille => self.render_braille(buffer, &renderer),
+      CanvasResolution::HalfBlock => self.render_half_blocks(buffer, &renderer),
+    }
+  }
+
+  /// The depths of the nearest and farthest front cells, leaving out text, or
+  /// `None` if there aren't any.
+  fn front_depth_range(&self) -> Option<(f32, f32)> {
+    self
+      .cells
+      .iter()
+      .filter_map(Zot::front)
+      // text like signs floats over the scene rather than being part of it
+      .filter(|cell| !cell.mat.is_glyph())
+      .map(|cell| cell.proj_depth)
+      .fold(None, |range, depth| match range {
+        None => Some((depth, depth)),
+        Some((nearest, farthest)) => {
+          Some((f32::min(nearest, depth), f32::max(farthest, depth)))
+        }
+      })
+  }
+
+  fn render_cells(&self, buffer: &mut Buffer, renderer: &CellRenderer) {
+    for (i, zot) in self.cells.iter().enumerate() {
+      let canvas_pos =
+        UVec2::new(i as u32 % self.extent.x, i as u32 / self.extent.x);
+      let Some(position) = buffer_position(buffer, canvas_pos) else {
         continue;
-      }
+      };
 
-      let cell = buffer.cell_mut((pos.x as u16, pos.y as u16)).unwrap();
-      match zot {
+      buffer[position] = match zot {
         Zot::Zero => continue,
-        Zot::One(a) => {
-          *cell = a.mat.render(None);
+        Zot::One(a) => renderer.render(&a.mat, None),
+        Zot::Two(a, b) => renderer.render(&a.mat, Some(&b.mat)),
+      };
+    }
+  }
+
+  /// Renders each cell's 2x4 canvas points as braille dots, colored by the
+  /// nearest dot. Glyphs like text are drawn as-is if they're in front, and
+  /// blank materials only draw a dot where they show what's behind them, like
+  /// a hidden line behind an occluder.
+  fn render_braille(&self, buffer: &mut Buffer, renderer: &CellRenderer) {
+    let cell_extent = self.extent / self.resolution.points_per_cell();
+
+    for cell_y in 0..cell_extent.y {
+      for cell_x in 0..cell_extent.x {
+        let mut nearest: Option<&UnpositionedDrawnCell> = None;
+        let mut nearest_dot: Option<&Zot<UnpositionedDrawnCell>> = None;
+        let mut dot_bits = 0;
+
+        for (y, row_bits) in BRAILLE_DOT_BITS.iter().enumerate() {
+          for (x, bit) in row_bits.iter().enumerate() {
+            let point = IVec2::new(
+              cell_x as i32 * 2 + x as i32,
+              cell_y as i32 * 4 + y as i32,
+            );
+            let Some(zot) =
+              canvas_index(point, self.extent).map(|index| &self.cells[index])
+            else {
+              continue;
+            };
+            let Some(front) = zot.front() else {
+              continue;
+            };
+
+            if nearest.is_none_or(|n| front < n) {
+              nearest = Some(front);
+            }
+            if front.mat.is_glyph() {
+              continue;
+            }
+            if front.mat.is_blank() && !renderer.shows_behind(zot) {
+              continue;
+            }
+            dot_bits |= bit;
+            if nearest_dot.and_then(Zot::front).is_none_or(|n| front < n) {
+              nearest_dot = Some(zot);
+            }
+          }
         }
-        Zot::Two(a, b) => {
-          *cell = a.mat.render(Some(&b.mat));
+
+        let Some(nearest) = nearest else {
+          continue;
+        };
+        let Some(position) =
+          buffer_position(buffer, UVec2::new(cell_x, cell_y))
+        else {
+          continue;
+        };
+
+        buffer[position] = match nearest_dot {
+          Some(dot) if !nearest.mat.is_glyph() => {
+            let mut cell = renderer.render_zot(dot).unwrap_or_default();
+            // the braille patterns block starts at U+2800, with one bit per dot
+            cell.set_char(char::from_u32(0x2800 + dot_bits as u32).unwrap());
+            cell
+          }
+          _ => renderer.render(&nearest.mat, None),
+        };
+      }
+    }
+  }
+
+  /// Renders each cell's two canvas points as the top and bottom halves of a
+  /// `▀`, each colored by its nearest material. Glyphs like text are drawn
+  /// as-is if they're in front, and empty halves keep the buffer's background.
+  fn render_half_blocks(&self, buffer: &mut Buffer, renderer: &CellRenderer) {
+    let cell_extent = self.extent / self.resolution.points_per_cell();
+
+    for cell_y in 0..cell_extent.y {
+      for cell_x in 0..cell_extent.x {
+        let [top, bottom] = [0, 1].map(|y| {
+          let point = IVec2::new(cell_x as i32, cell_y as i32 * 2 + y);
+          canvas_index(point, self.extent)
+            .and_then(|index| self.cells[index].front())
+        });
+
+        let Some(nearest) = top.into_iter().chain(bottom).min() else {
+          continue;
+        };
+        let Some(position) =
+          buffer_position(buffer, UVec2::new(cell_x, cell_y))
+        else {
+          continue;
+        };
+
+        if nearest.mat.is_glyph() {
+          buffer[position] = renderer.render(&nearest.mat, None);
+          continue;
         }
+
+        let background = buffer[position].bg;
+        let [top_color, bottom_color] = [top, bottom].map(|half| {
+          half.map_or(background, |half| renderer.pixel_color(&half.mat))
+        });
+
+        let mut cell = Cell::default();
+        cell.set_symbol("▀");
+        cell.set_fg(top_color);
+        cell.set_bg(bottom_color);
+        buffer[position] = cell;
+      }
+    }
+  }
+}
+
+/// Renders drawn materials for a single frame of a camera.
+struct CellRenderer<'a> {
+  theme: &'a Theme,
+  cue:   DepthCueFrame<'a>,
+}
+
+impl CellRenderer<'_> {
+  /// Renders `drawn` against the cell behind it, faded by the depth cue.
+  fn render(
+    &self,
+    drawn: &DrawnMaterial,
+    behind: Option<&DrawnMaterial>,
+  ) -> Cell {
+    let mut cell = drawn.render(behind, self.theme);
+    let shown = shown_material(drawn, behind, &cell);
+    self.cue.apply(&mut cell, shown);
+    cell
+  }
+
+  /// Renders the front cell of `zot` against the one behind it, if it has a
+  /// front cell.
+  fn render_zot(&self, zot: &Zot<UnpositionedDrawnCell>) -> Option<Cell> {
+    let front = zot.front()?;
+    Some(self.render(&front.mat, zot.behind().map(|behind| &behind.mat)))
+  }
+
+  /// Whether the blank front cell of `zot` shows what's behind it, like a
+  /// hidden line behind an occluder.
+  fn shows_behind(&self, zot: &Zot<UnpositionedDrawnCell>) -> bool {
+    zot.behind().is_some()
+      && self
+        .render_zot(zot)
+        .is_some_and(|cell| !cell.symbol().trim().is_empty())
+  }
+
+  /// The color `drawn` fills a pixel with when it's drawn as a solid block:
+  /// its background if it's blank, otherwise its stroke color.
+  fn pixel_color(&self, drawn: &DrawnMaterial) -> Color {
+    let cell = self.render(drawn, None);
+    if drawn.is_blank() { cell.bg } else { cell.fg }
+  }
+}
+
+/// The material whose symbol `cell` shows, after rendering `drawn` against
+/// `behind`. That's `drawn` itself, unless it's a blank face showing what's
+/// behind it, like a line seen through a face or drawn hidden behind an
+/// occluder.
+fn shown_material<'a>(
+  drawn: &'a DrawnMaterial,
+  behind: Option<&'a DrawnMaterial>,
+  cell: &Cell,
+) -> &'a DrawnMaterial {
+  match behind {
+    Some(behind) if drawn.is_blank() && !cell.symbol().trim().is_empty() => {
+      if drawn.is_occluder() {
+        behind.hidden().unwrap_or(behind)
+      } else {
+        behind
       }
     }
+    _ => drawn,
+  }
+}
 
-    buffer
+/// The position in `buffer` of a terminal cell, relative to the origin of the
+/// buffer's area, or `None` if it's outside of it.
+fn buffer_position(buffer: &Buffer, cell: UVec2) -> Option<Position> {
+  let area = buffer.area;
+  let position = Position {
+    x: area.x.saturating_add(cell.x as _),
+    y: area.y.saturating_add(cell.y as _),
+  };
+  area.contains(position).then_some(position)
+}
+
+/// The row-major index of a canvas position within a canvas of size `extent`,
+/// or `None` if it's off the canvas.
+fn canvas_index(position: IVec2, extent: UVec2) -> Option<usize> {
+  let position = UVec2::try_from(position).ok()?;
+  if position.x >= extent.x || position.y >= extent.y {
+    return None;
   }
+  Some((position.y * extent.x + position.x) as usize)
 }
 
 #[derive(Clone, Default)]
@@ -177,8 +437,16 @@ enum Zot<T> {
   Two(T, T),
 }
 
-impl<T: Ord> Zot<T> {
-  /// Keeps the lowest 2 values (according to `Ord`) when it already has 2.
+/// A value kept in a [`Zot`].
+trait Layered: Ord {
+  /// Whether this hides what's behind it without drawing anything, so is only
+  /// worth keeping behind the front value if nothing else is.
+  fn is_occluder(&self) -> bool;
+}
+
+impl<T: Layered> Zot<T> {
+  /// Keeps the lowest value (according to `Ord`), and the lowest of the rest
+  /// preferring ones that aren't occluders, when it already has 2.
   fn add_inner(self, value: T) -> Self {
     match self {
       Zot::Zero => Zot::One(value),
@@ -191,8 +459,12 @@ impl<T: Ord> Zot<T> {
       Zot::Two(a, b) => {
         let mut set = [a, b, value];
         set.sort();
-        let [a, b, _] = set;
-        Zot::Two(a, b)
+        let [a, b, c] = set;
+        if b.is_occluder() && !c.is_occluder() {
+          Zot::Two(a, c)
+        } else {
+          Zot::Two(a, b)
+        }
       }
     }
   }
@@ -202,3 +474,21 @@ impl<T: Ord> Zot<T> {
     *self = val.add_inner(value);
   }
 }
+
+impl<T> Zot<T> {
+  /// The frontmost value, if there is one.
+  fn front(&self) -> Option<&T> {
+    match self {
+      Zot::Zero => None,
+      Zot::One(a) | Zot::Two(a, _) => Some(a),
+    }
+  }
+
+  /// The value behind the front one, if there is one.
+  fn behind(&self) -> Option<&T> {
+    match self {
+      Zot::Two(_, b) => Some(b),
+      _ => None,
+    }
+  }
+}
@@ -51,38 +51,42 @@ impl DrawnShape for SignArgs<'_> {
       .render_ref(content_size, &mut intermediate_buffer);
 
     let world_space_anchor = transform.transform_point(self.position);
+
+    // the anchor can still be off the sides of the canvas, but it needs a
+    // sensible depth
+    let anchor_depth = args.world_to_ndc(world_space_anchor).z;
+    if !(0.0..=1.0).contains(&anchor_depth) {
+      return;
+    }
+
     let projected_anchor = args.world_to_canvas_coords(world_space_anchor);
+    // the sign is laid out in cells, which can span several canvas points
+    let points_per_cell = args.points_per_cell().as_ivec2();
+    let canvas_half_extents = content_half_extents * points_per_cell.as_vec2();
+
     // the projected anchor point, plus the anchor position in canvas directions
     let content_center = projected_anchor.pos()
-      + (content_half_extents * Vec2::new(-self.anchor.x, self.anchor.y))
+      + (canvas_half_extents * Vec2::new(-self.anchor.x, self.anchor.y))
         .round()
         .as_ivec2();
     // the point the paragraph is drawn from is the top left
-    let content_origin = content_center - content_half_extents.as_ivec2();
+    let content_origin = content_center - canvas_half_extents.as_ivec2();
 
     for (i, cell) in intermediate_buffer.content().iter().enumerate() {
       let buffer_pos = intermediate_buffer.pos_of(i);
       let buffer_pos = IVec2::new(buffer_pos.0 as _, buffer_pos.1 as _);
-      let canvas_pos = content_origin + buffer_pos;
+      let canvas_pos = content_origin + buffer_pos * points_per_cell;
       let depth = match self.on_top {
         true => 0.0,
         false => projected_anchor.depth(),
       };
 
-      let material = Material::Text {
-        text:     cell.symbol().into(),
-        fg_color: cell.fg,
-        bg_color: cell.bg,
-      };
-      let material_draw_request_type = material.draw_request_type();
-      let material_draw_request = match material_draw_request_type {
-        super::MaterialDrawRequestType::None => MaterialDrawRequest::None,
-        super::MaterialDrawRequestType::Neighbors => {
-          panic!("Text cannot have line neighbors.");
-        }
+      let material = Material::text(cell.symbol().into(), cell.fg, cell.bg);
+      let Some(drawn_material) =
+        material.draw_or_warn(MaterialDrawRequest::None, depth)
+      else {
+        continue;
       };
-
-      let drawn_material = material.draw(material_draw_request, depth);
       buffer.draw(drawn_material, ProjectedPoint::new(canvas_pos, depth));
     }
   }
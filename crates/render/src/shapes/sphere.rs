@@ -0,0 +1,107 @@
+use std::f32::consts::PI;
+
+use bevy::prelude::*;
+
+use super::{
+  CanvasArgs, DrawnShape, LocalCircle, RoundStyle, ShapeBuffer,
+  draw_circle_arcs,
+};
+use crate::culling::ShapeBounds;
+
+/// A sphere around the origin, drawn as its silhouette with optional
+/// latitude and longitude lines over the side facing the camera.
+pub struct SphereArgs {
+  pub radius:     f32,
+  /// The number of latitude lines, spaced evenly between the poles.
+  pub latitudes:  u32,
+  /// The number of great circles drawn through the poles, which makes twice
+  /// as many lines of longitude.
+  pub longitudes: u32,
+  pub style:      RoundStyle,
+}
+
+impl DrawnShape for SphereArgs {
+  fn draw(
+    &self,
+    buffer: &mut ShapeBuffer,
+    args: &CanvasArgs,
+    transform: &Transform,
+  ) {
+    let SphereArgs {
+      radius,
+      latitudes,
+      longitudes,
+      style,
+    } = self;
+    let radius = *radius;
+    let eye = args.local_camera_center(transform);
+    let eye_length = eye.xyz().length();
+
+    // the silhouette is the circle of points whose tangent plane passes
+    // through the camera, which all lie in the plane `p · e = r² * w`
+    let offset = radius * radius * eye.w / eye_length;
+    if eye_length <= f32::EPSILON || offset.abs() >= radius {
+      // the camera is inside the sphere
+      return;
+    }
+    let normal = eye.xyz() / eye_length;
+    let (u, v) = normal.any_orthonormal_pair();
+    let silhouette_radius = (radius * radius - offset * offset).sqrt();
+    let silhouette = LocalCircle {
+      center: normal * offset,
+      u:      u * silhouette_radius,
+      v:      v * silhouette_radius,
+    };
+    draw_circle_arcs(
+      silhouette,
+      |_| 1.0,
+      &style.material,
+      style.pattern,
+      buffer,
+      args,
+      transform,
+    );
+
+    let Some(guide_material) = &style.guide_material else {
+      return;
+    };
+    let facing = |point: Vec3| point.dot(eye.xyz()) - radius * radius * eye.w;
+
+    for i in 1..=*latitudes {
+      let latitude = PI * (i as f32 / (*latitudes + 1) as f32 - 0.5);
+      let circle =
+        LocalCircle::around_y(radius * latitude.sin(), radius * latitude.cos());
+      draw_circle_arcs(
+        circle,
+        facing,
+        guide_material,
+        style.guide_pattern,
+        buffer,
+        args,
+        transform,
+      );
+    }
+
+    for i in 0..*longitudes {
+      let longitude = PI * i as f32 / *longitudes as f32;
+      let circle = LocalCircle {
+        center: Vec3::ZERO,
+        u:      Vec3::Y * radius,
+        v:      Vec3::new(longitude.cos(), 0.0, longitude.sin()) * radius,
+      };
+      draw_circle_arcs(
+        circle,
+        facing,
+        guide_material,
+        style.guide_pattern,
+        buffer,
+        args,
+        transform,
+      );
+    }
+  }
+
+  fn bounds(&self) -> Option<ShapeBounds> {
+    Some(ShapeBounds::new(Vec3::ZERO, Vec3::splat(self.radius)))
+  }
+}
@@ -0,0 +1,278 @@
+//! Snapshots of what the built-in shapes draw, rendered with the
+//! [`HeadlessRenderer`]. Run with `UPDATE_SNAPSHOTS=1` to accept changes.
+
+use std::path::PathBuf;
+
+use bevy::prelude::*;
+use ratatui::widgets::Paragraph;
+
+use crate::{
+  camera::{Camera, CanvasResolution},
+  headless::{HeadlessRenderer, assert_snapshot, buffer_to_plain_text},
+  shapes::{
+    CircleArgs, CircleStyle, CuboidArgs, CuboidStyle, HiddenLineStyle,
+    LinePattern, LineVariant, Material, SignArgs,
+    thin_neighbor::{Neighbor, thin_neighbor_symbol},
+  },
+};
+
+const SIZE: UVec2 = UVec2::new(48, 20);
+
+fn snapshot_path(name: &str) -> PathBuf {
+  PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+    .join("snapshots")
+    .join(format!("{name}.snap"))
+}
+
+/// A renderer looking down at the origin from the front and above.
+fn renderer() -> HeadlessRenderer {
+  renderer_with(Camera::default().with_scale(0.1))
+}
+
+/// A renderer like [`renderer`], but through `camera`.
+fn renderer_with(camera: Camera) -> HeadlessRenderer {
+  HeadlessRenderer::new(
+    SIZE,
+    camera,
+    Transform::from_xyz(0.0, 8.0, 14.0).looking_at(Vec3::ZERO, Vec3::Y),
+  )
+}
+
+fn wall_cuboid_style() -> CuboidStyle {
+  CuboidStyle {
+    line_material:   Material::wall_edge(),
+    corner_material: Some(Material::wall_corner()),
+    face_material:   None,
+    line_variant:    LineVariant::Thin,
+    line_pattern:    LinePattern::Solid,
+    hidden_line:     None,
+    face_tints:      None,
+    face_shading:    None,
+  }
+}
+
+#[test]
+fn cuboid() {
+  let mut renderer = renderer();
+  renderer.spawn_shape(
+    CuboidArgs {
+      half_extents: Vec3::new(3.0, 2.0, 2.0),
+      style:        wall_cuboid_style(),
+    },
+    Transform::from_rotation(Quat::from_rotation_y(0.4)),
+  );
+
+  let frame = renderer.render_frame();
+  assert_snapshot(snapshot_path("cuboid"), &buffer_to_plain_text(&frame));
+}
+
+#[test]
+fn cuboid_with_faces() {
+  let mut renderer = renderer();
+  renderer.spawn_shape(
+    CuboidArgs {
+      half_extents: Vec3::new(3.0, 2.0, 2.0),
+      style:        CuboidStyle {
+        face_material: Some(Material::wall_face()),
+        ..wall_cuboid_style()
+      },
+    },
+    Transform::from_rotation(Quat::from_rotation_y(0.4)),
+  );
+
+  let frame = renderer.render_frame();
+  assert_snapshot(
+    snapshot_path("cuboid_with_faces"),
+    &buffer_to_plain_text(&frame),
+  );
+}
+
+#[test]
+fn cuboid_hidden_lines() {
+  let mut renderer = renderer();
+  renderer.spawn_shape(
+    CuboidArgs {
+      half_extents: Vec3::new(3.0, 2.0, 2.0),
+      style:        CuboidStyle {
+        face_material: Some(Material::occluder()),
+        hidden_line: Some(HiddenLineStyle {
+          material: Material::wall_edge(),
+          pattern:  LinePattern::Dashed { dash: 1, gap: 1 },
+        }),
+        ..wall_cuboid_style()
+      },
+    },
+    Transform::from_rotation(Quat::from_rotation_y(0.4)),
+  );
+
+  let frame = renderer.render_frame();
+  assert_snapshot(
+    snapshot_path("cuboid_hidden_lines"),
+    &buffer_to_plain_text(&frame),
+  );
+}
+
+#[test]
+fn cuboid_hidden_lines_braille() {
+  let mut renderer = renderer_with(
+    Camera::default()
+      .with_scale(0.1)
+      .with_resolution(CanvasResolution::Braille),
+  );
+  renderer.spawn_shape(
+    CuboidArgs {
+      half_extents: Vec3::new(3.0, 2.0, 2.0),
+      style:        CuboidStyle {
+        face_material: Some(Material::occluder()),
+        hidden_line: Some(HiddenLineStyle {
+          material: Material::wall_edge(),
+          pattern:  LinePattern::Dashed { dash: 1, gap: 1 },
+        }),
+        ..wall_cuboid_style()
+      },
+    },
+    Transform::from_rotation(Quat::from_rotation_y(0.4)),
+  );
+
+  let frame = renderer.render_frame();
+  assert_snapshot(
+    snapshot_path("cuboid_hidden_lines_braille"),
+    &buffer_to_plain_text(&frame),
+  );
+}
+
+#[test]
+fn circle() {
+  let mut renderer = renderer();
+  renderer.spawn_shape(
+    CircleArgs {
+      radius: 4.0,
+      plane:  Quat::IDENTITY,
+      style:  CircleStyle {
+        material: Material::wall_edge(),
+        variant:  LineVariant::Thin,
+        pattern:  LinePattern::Solid,
+        hidden:   None,
+        fill:     None,
+      },
+    },
+    Transform::IDENTITY,
+  );
+
+  let frame = renderer.render_frame();
+  assert_snapshot(snapshot_path("circle"), &buffer_to_plain_text(&frame));
+}
+
+#[test]
+fn circle_rounded() {
+  let mut renderer = renderer();
+  renderer.spawn_shape(
+    CircleArgs {
+      radius: 4.0,
+      plane:  Quat::IDENTITY,
+      style:  CircleStyle {
+        material: Material::wall_edge(),
+        variant:  LineVariant::Rounded,
+        pattern:  LinePattern::Solid,
+        hidden:   None,
+        fill:     None,
+      },
+    },
+    Transform::IDENTITY,
+  );
+
+  let frame = renderer.render_frame();
+  assert_snapshot(
+    snapshot_path("circle_rounded"),
+    &buffer_to_plain_text(&frame),
+  );
+}
+
+#[test]
+fn circle_hidden_lines() {
+  let mut renderer = renderer();
+  renderer.spawn_shape(
+    CuboidArgs {
+      half_extents: Vec3::splat(2.0),
+      style:        CuboidStyle {
+        face_material: Some(Material::occluder()),
+        ..wall_cuboid_style()
+      },
+    },
+    Transform::IDENTITY,
+  );
+  // a ring behind the cuboid, partly hidden by it
+  renderer.spawn_shape(
+    CircleArgs {
+      radius: 2.5,
+      plane:  Quat::IDENTITY,
+      style:  CircleStyle {
+        material: Material::wall_edge(),
+        variant:  LineVariant::Thin,
+        pattern:  LinePattern::Solid,
+        hidden:   Some(HiddenLineStyle {
+          material: Material::wall_edge(),
+          pattern:  LinePattern::Dotted { gap: 1 },
+        }),
+        fill:     None,
+      },
+    },
+    Transform::from_xyz(0.0, 0.0, -3.0),
+  );
+
+  let frame = renderer.render_frame();
+  assert_snapshot(
+    snapshot_path("circle_hidden_lines"),
+    &buffer_to_plain_text(&frame),
+  );
+}
+
+#[test]
+fn sign() {
+  let mut renderer = renderer();
+  renderer.spawn_shape(
+    SignArgs {
+      content:    Paragraph::new("hull breach\nin sector 7"),
+      min_width:  None,
+      max_width:  16,
+      max_height: None,
+      position:   Vec3::ZERO,
+      anchor:     Vec2::new(-1.0, 1.0),
+      on_top:     false,
+    },
+    Transform::from_xyz(-2.0, 1.0, 0.0),
+  );
+
+  let frame = renderer.render_frame();
+  assert_snapshot(snapshot_path("sign"), &buffer_to_plain_text(&frame));
+}
+
+#[test]
+fn thin_neighbor_symbols() {
+  use Neighbor::*;
+  let neighbors = [
+    TopLeft,
+    Top,
+    TopRight,
+    Left,
+    Right,
+    BottomLeft,
+    Bottom,
+    BottomRight,
+  ];
+
+  // one row per `from` neighbor, one column per `to` neighbor
+  let table = neighbors
+    .iter()
+    .map(|from| {
+      let row = neighbors
+        .iter()
+        .map(|to| thin_neighbor_symbol(*from, *to))
+        .collect::<Vec<_>>()
+        .join(" ");
+      format!("{:<12}{row}\n", format!("{from:?}"))
+    })
+    .collect::<String>();
+
+  assert_snapshot(snapshot_path("thin_neighbor_symbols"), &table);
+}
@@ -0,0 +1,314 @@
+//! The colors everything is drawn with, picked at runtime.
+//!
+//! The [`Theme`] resource holds a color for each role, like lineart or titles,
+//! and defaults to the palette in the `colors` crate. [`ThemePlugin`] loads it
+//! from a RON file instead, and reloads it whenever the file changes:
+//!
+//! ```ron
+//! (
+//!   base: "#111110",
+//!   lineart: "#7c7b74",
+//!   title: "#ffa057",
+//! )
+//! ```
+//!
+//! Roles left out of the file keep their default colors. Colors can be
+//! anything `ratatui` parses, like `"#rrggbb"`, `"red"` or a 256-color index.
+//! A `color_depth` of `"truecolor"`, `"256"` or `"16"` overrides the detected
+//! [`ColorDepth`], though the environment variable still wins over it.
+
+use std::{
+  fmt,
+  path::{Path, PathBuf},
+  str::FromStr,
+  time::{Duration, SystemTime},
+};
+
+use bevy::prelude::*;
+use ratatui::{buffer::Cell, style::Color};
+use serde::Deserialize;
+
+use crate::color_depth::ColorDepth;
+
+/// How often the theme file is checked for changes.
+const RELOAD_CHECK_PERIOD: Duration = Duration::from_secs(1);
+
+/// The colors everything is drawn with, by the role they play.
+#[derive(Resource, Clone, Debug, PartialEq, Eq)]
+pub struct Theme {
+  /// The background of the world.
+  pub base:          Color,
+  /// The background of UI panels.
+  pub background:    Color,
+  /// Lines and points drawn in the world.
+  pub lineart:       Color,
+  pub dim_text:      Color,
+  pub normal_text:   Color,
+  /// Text that should stand out, like values.
+  pub punchy_text:   Color,
+  pub title:         Color,
+  pub normal_border: Color,
+  pub active_border: Color,
+  /// How many colors the terminal can show. Everything is quantized to it
+  /// once it's drawn.
+  pub color_depth:   ColorDepth,
+}
+
+impl Default for Theme {
+  fn default() -> Self {
+    Theme {
+      base:          colors::BASE_COLOR_RATATUI,
+      background:    colors::BACKGROUND_COLOR_RATATUI,
+      lineart:       colors::LINEART_COLOR_RATATUI,
+      dim_text:      colors::DIM_TEXT_COLOR_RATATUI,
+      normal_text:   colors::NORMAL_TEXT_COLOR_RATATUI,
+      punchy_text:   colors::PUNCHY_TEXT_COLOR_RATATUI,
+      title:         colors::TITLE_COLOR_RATATUI,
+      normal_border: colors::NORMAL_BORDER_COLOR_RATATUI,
+      active_border: colors::ACTIVE_BORDER_COLOR_RATATUI,
+      color_depth:   ColorDepth::detect(),
+    }
+  }
+}
+
+impl Theme {
+  /// Parses a theme from RON, using the default colors for any roles it
+  /// leaves out.
+  pub fn from_ron(source: &str) -> Result<Self, ThemeLoadError> {
+    // roles are optional, but are written without `Some(..)`
+    let file: ThemeFile = ron::Options::default()
+      .with_default_extension(ron::extensions::Extensions::IMPLICIT_SOME)
+      .from_str(source)
+      .map_err(ThemeLoadError::Ron)?;
+    let mut theme = Theme::default();
+
+    if let Some(value) = file.color_depth {
+      let color_depth = value
+        .parse()
+        .map_err(|_| ThemeLoadError::ColorDepth(value.clone()))?;
+      if ColorDepth::from_env_override().is_none() {
+        theme.color_depth = color_depth;
+      }
+    }
+
+    let roles = [
+      ("base", file.base, &mut theme.base),
+      ("background", file.background, &mut theme.background),
+      ("lineart", file.lineart, &mut theme.lineart),
+      ("dim_text", file.dim_text, &mut theme.dim_text),
+      ("normal_text", file.normal_text, &mut theme.normal_text),
+      ("punchy_text", file.punchy_text, &mut theme.punchy_text),
+      ("title", file.title, &mut theme.title),
+      (
+        "normal_border",
+        file.normal_border,
+        &mut theme.normal_border,
+      ),
+      (
+        "active_border",
+        file.active_border,
+        &mut theme.active_border,
+      ),
+    ];
+    for (role, value, color) in roles {
+      let Some(value) = value else {
+        continue;
+      };
+      *color = Color::from_str(&value).map_err(|_| ThemeLoadError::Color {
+        role,
+        value: value.clone(),
+      })?;
+    }
+
+    Ok(theme)
+  }
+
+  /// Reads and parses a theme file.
+  pub fn load(path: impl AsRef<Path>) -> Result<Self, ThemeLoadError> {
+    let source = std::fs::read_to_string(path).map_err(ThemeLoadError::Io)?;
+    Self::from_ron(&source)
+  }
+
+  /// The cell the world is cleared to before anything is drawn.
+  pub fn default_cell(&self) -> Cell {
+    let mut cell = Cell::EMPTY;
+    cell.bg = self.base;
+    cell.fg = self.punchy_text;
+    cell
+  }
+}
+
+/// A theme as it's written in a file, before its colors are parsed.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ThemeFile {
+  base:          Option<String>,
+  background:    Option<String>,
+  lineart:       Option<String>,
+  dim_text:      Option<String>,
+  normal_text:   Option<String>,
+  punchy_text:   Option<String>,
+  title:         Option<String>,
+  normal_border: Option<String>,
+  active_border: Option<String>,
+  color_depth:   Option<String>,
+}
+
+/// An error from loading a theme file.
+#[derive(Debug)]
+pub enum ThemeLoadError {
+  /// The file couldn't be read.
+  Io(std::io::Error),
+  /// The file isn't a valid theme.
+  Ron(ron::error::SpannedError),
+  /// A role's color couldn't be parsed.
+  Color { role: &'static str, value: String },
+  /// The color depth couldn't be parsed.
+  ColorDepth(String),
+}
+
+impl fmt::Display for ThemeLoadError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      ThemeLoadError::Io(e) => write!(f, "failed to read theme file: {e}"),
+      ThemeLoadError::Ron(e) => write!(f, "failed to parse theme file: {e}"),
+      ThemeLoadError::Color { role, value } => {
+        write!(f, "failed to parse `{value}` as a color for `{role}`")
+      }
+      ThemeLoadError::ColorDepth(value) => {
+        write!(f, "failed to parse `{value}` as a color depth")
+      }
+    }
+  }
+}
+
+impl std::error::Error for ThemeLoadError {}
+
+/// Where the theme is loaded from, and when it was last loaded.
+#[derive(Resource)]
+struct ThemeSource {
+  path:     PathBuf,
+  /// When the file was last modified, as of the last time it was loaded.
+  modified: Option<SystemTime>,
+  timer:    Timer,
+}
+
+/// Reloads the theme when its file has been modified since it was last
+/// loaded. A theme that fails to load is logged, and the current one is kept.
+fn reload_theme(
+  time: Res<Time<Real>>,
+  mut source: ResMut<ThemeSource>,
+  mut theme: ResMut<Theme>,
+) {
+  if !source.timer.tick(time.delta()).just_finished() {
+    return;
+  }
+
+  let modified = modified_time(&source.path);
+  if modified.is_none() || modified == source.modified {
+    return;
+  }
+  source.modified = modified;
+
+  match Theme::load(&source.path) {
+    Ok(loaded) => {
+      theme.set_if_neq(loaded);
+    }
+    Err(e) => error!("keeping the current theme: {e}"),
+  }
+}
+
+fn modified_time(path: &Path) -> Option<SystemTime> {
+  std::fs::metadata(path)
+    .and_then(|metadata| metadata.modified())
+    .ok()
+}
+
+/// Loads the [`Theme`] from a RON file, and reloads it whenever the file
+/// changes. Relative paths are relative to the working directory.
+pub struct ThemePlugin {
+  pub path: PathBuf,
+}
+
+impl ThemePlugin {
+  pub fn new(path: impl Into<PathBuf>) -> Self { Self { path: path.into() } }
+}
+
+impl Plugin for ThemePlugin {
+  fn build(&self, app: &mut App) {
+    // load it straight away, so the first frame is drawn with it
+    let modified = modified_time(&self.path);
+    let theme = Theme::load(&self.path).unwrap_or_else(|e| {
+      error!("using the default theme: {e}");
+      Theme::default()
+    });
+
+    app
+      .insert_resource(theme)
+      .insert_resource(ThemeSource {
+        path: self.path.clone(),
+        modified,
+        timer: Timer::new(RELOAD_CHECK_PERIOD, TimerMode::Repeating),
+      })
+      .add_systems(PreUpdate, reload_theme);
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn loads_the_bundled_themes() {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("../../themes");
+    let mut loaded = 0;
+    for entry in std::fs::read_dir(&dir).unwrap() {
+      let path = entry.unwrap().path();
+      if path.extension().is_some_and(|ext| ext == "ron") {
+        if let Err(e) = Theme::load(&path) {
+          panic!("failed to load {}: {e}", path.display());
+        }
+        loaded += 1;
+      }
+    }
+    assert!(loaded > 0, "no themes found in {}", dir.display());
+  }
+
+  #[test]
+  fn roles_left_out_keep_their_defaults() {
+    let theme = Theme::from_ron(r##"(title: "#ff0000")"##).unwrap();
+    assert_eq!(theme.title, Color::Rgb(255, 0, 0));
+    assert_eq!(
+      Theme {
+        title: Theme::default().title,
+        color_depth: Theme::default().color_depth,
+        ..theme
+      },
+      Theme::default()
+    );
+  }
+
+  #[test]
+  fn rejects_unknown_roles() {
+    let result = Theme::from_ron(r##"(title: "#ff0000", outline: "#000000")"##);
+    assert!(matches!(result, Err(ThemeLoadError::Ron(_))));
+  }
+
+  #[test]
+  fn rejects_invalid_colors() {
+    let result = Theme::from_ron(r#"(lineart: "not a color")"#);
+    assert!(matches!(
+      result,
+      Err(ThemeLoadError::Color {
+        role: "lineart",
+        ..
+      })
+    ));
+  }
+
+  #[test]
+  fn rejects_invalid_color_depths() {
+    let result = Theme::from_ron(r#"(color_depth: "millions")"#);
+    assert!(matches!(result, Err(ThemeLoadError::ColorDepth(_))));
+  }
+}